@@ -9,12 +9,32 @@ use std::rc::Rc;
 
 use crate::{wm, NAME};
 use crate::components::{Component, ComponentParams, load_component};
-use crate::config::ConfigGroup;
-use crate::wm::ipc::commands::Selectors;
+use crate::config::{ConfigGroup, Monitor};
+
+options! {
+    pub struct BarOptions {
+        "class" => class: Option<String> = None,
+        /// an index, output name, "primary" or "all"
+        "monitor" => monitor: Monitor = 0,
+        /// top | bottom
+        "position" => position: String = "top",
+        /// components to add, identified by name
+        "layout" => layout: Vec<String> = vec![],
+        /// bind workspace next/prev to scroll events
+        "workspace-scroll" => workspace_scroll: bool = false,
+        /// reserve space on the desktop
+        "reserve-space" => reserve_space: bool = true,
+        /// start hidden
+        "hidden" => hidden: bool = false,
+        /// disable shadows in compton
+        "disable-shadow" => disable_shadow: bool = true,
+    }
+}
 
 pub struct Bar {
     config: ConfigGroup,
-    components: Vec<Box<dyn Component>>,
+    // paired with the name from their config
+    components: Vec<(String, Box<dyn Component>)>,
     overlay: Overlay,
     container: gtk::Box,
     event_ids: Vec<SignalHandlerId>,
@@ -28,8 +48,9 @@ impl Bar {
         monitor: &Rectangle,
         existing_window: Option<Window>,
     ) -> Bar {
+        let options = BarOptions::from_config(&config);
         // TODO: check if the type differs to existing window
-        let reserve_space = config.get_bool_or("reserve-space", true);
+        let reserve_space = options.reserve_space;
         let window_type = if reserve_space {
             WindowType::Toplevel
         } else {
@@ -73,7 +94,7 @@ impl Bar {
         // set gdk::EventMask::SCROLL_MASK and disable 'smooth' scrolling
         viewport.add_events(2097152);
         // when scrolling, change workspace
-        if config.get_bool_or("workspace-scroll", false) {
+        if options.workspace_scroll {
             viewport.connect_scroll_event(clone!(wm_util move |_vp, e| {
                 let direction = e.get_direction();
                 let is_next = direction == ScrollDirection::Down;
@@ -89,7 +110,7 @@ impl Bar {
         window.add(&viewport);
 
         // set position
-        let is_top = config.get_str_or("position", "top") == "top";
+        let is_top = options.position == "top";
         let &Rectangle { x, y, height, .. } = monitor;
         let is_set = Rc::new(RefCell::new(false));
         let size_id = window.connect_size_allocate(clone!((is_set, wm_util)
@@ -134,7 +155,7 @@ impl Bar {
 
         // show window (needs to do this at least once)
         window.show_all();
-        if config.get_bool_or("hidden", false) {
+        if options.hidden {
             window.hide();
         }
 
@@ -148,7 +169,7 @@ impl Bar {
             event_ids,
         };
 
-        if is_new && bar.config.get_bool_or("disable-shadow", true) {
+        if is_new && options.disable_shadow {
             wm::gtk::disable_shadow(&bar.window);
         }
 
@@ -171,7 +192,7 @@ impl Bar {
 
     fn unload(&self) {
         // destroy components
-        for component in self.components.iter() {
+        for (_name, component) in self.components.iter() {
             component.destroy();
         }
         // remove events
@@ -221,23 +242,40 @@ impl wm::Window for Bar {
         self.config.get_int_or("monitor", 0) as _
     }
 
+    fn get_config(&self) -> &ConfigGroup {
+        &self.config
+    }
+
+    fn get_type(&self) -> &'static str {
+        "bar"
+    }
+
+    fn is_visible(&self) -> bool {
+        self.window.is_visible()
+    }
+
     fn add_component(&mut self, component: Box<dyn Component>) {
-        self.components.push(component);
+        self.components.push((String::new(), component));
     }
 
     fn load_component(&mut self, config: ConfigGroup, container: &gtk::Box, wm_util: &wm::WMUtil) {
+        let name = config.name.clone();
         load_component(ComponentParams {
             container,
             config,
             window: Box::new(self),
             wm_util,
         });
+        // nested components have already been named
+        self.components.iter_mut()
+            .filter(|(component_name, _)| component_name.is_empty())
+            .for_each(|(component_name, _)| *component_name = name.clone());
     }
 
-    fn matches_selectors(&self, selectors: &Selectors) -> bool {
-        selectors.contains_id(&self.config.name) || {
-            let class = self.config.get_string("class");
-            class.is_some() && selectors.contains_class(&class.unwrap())
-        }
+    fn refresh_components(&self, names: &[String]) -> usize {
+        self.components.iter()
+            .filter(|(name, _)| names.contains(name))
+            .map(|(_, component)| component.refresh())
+            .count()
     }
 }
@@ -1,24 +1,29 @@
 use inotify::{Inotify, WatchMask};
-use crossbeam_channel as channel;
-use std::{thread, time};
+use std::os::unix::io::AsRawFd;
+use std::thread;
 use crate::components::{Component, ComponentParams};
 use gtk::prelude::*;
-use crate::util::{read_file, SymbolFmt, Timer};
+use crate::util::{self, read_file, Listener, SymbolFmt, Wakeup};
 use gtk::Label;
 
+options! {
+    pub struct BacklightOptions {
+        /// symbols are; percent
+        "format" => format: String = "{percent}",
+    }
+}
+
 pub struct Backlight {
     label: Label,
-    timer: Timer,
-    watcher: channel::Sender<()>,
+    listener: Listener,
+    wakeup: Wakeup,
 }
 
 impl Component for Backlight {
     fn destroy(&self) {
         self.label.destroy();
-        self.timer.remove();
-        if let Err(err) = self.watcher.send(()) {
-            error!("unwatch signal not sent - {}", err);
-        }
+        self.listener.remove();
+        self.wakeup.wake();
     }
 }
 
@@ -32,15 +37,22 @@ fn get_value(name: &str) -> Result<f32, String> {
 impl Backlight {
     pub fn init(params: ComponentParams) {
         let ComponentParams { config, window, container, .. } = params;
+        let options = BacklightOptions::from_config(&config);
         match get_value("brightness") {
             Ok(initial) => {
+                let (wakeup, woken) = match util::wakeup() {
+                    Ok(wakeup) => wakeup,
+                    Err(err) => {
+                        error!("{}", err);
+                        return;
+                    },
+                };
+
                 let label = Label::new(None);
                 super::init_widget(&label, &config, &window, container);
                 label.show();
 
-                let (s, r) = channel::unbounded();
-                let (s_dead, r_dead) = channel::unbounded();
-
+                let (s, r) = util::channel();
                 let max = get_value("max_brightness").unwrap_or(initial);
 
                 if let Err(err) = s.send((initial/max)*100.) {
@@ -57,8 +69,18 @@ impl Backlight {
 
                     match wd_res {
                         Ok(wd) => {
+                            let fds = [inotify.as_raw_fd(), woken.as_raw_fd()];
                             let mut buffer = [0; 1024];
                             loop {
+                                // sleep until the brightness changes or the component is destroyed
+                                match util::poll_readable(&fds, None) {
+                                    Ok(ref readable) if !readable[1] => {},
+                                    Ok(_) => break,
+                                    Err(err) => {
+                                        error!("{}", err);
+                                        break;
+                                    },
+                                }
                                 let events = inotify.read_events(&mut buffer)
                                     .expect("error reading events");
                                 for _ in events {
@@ -67,13 +89,8 @@ impl Backlight {
                                         error!("{}", err);
                                     }
                                 }
-                                if r_dead.try_recv().is_ok() {
-                                    inotify.rm_watch(wd).ok();
-                                    break;
-                                } else {
-                                    thread::sleep(time::Duration::from_millis(50));
-                                }
                             }
+                            inotify.rm_watch(wd).ok();
                         },
                         Err(err) => {
                             error!("{}", err.to_string());
@@ -81,22 +98,20 @@ impl Backlight {
                     }
                 });
 
-                let symbols = SymbolFmt::new(config.get_str_or("format", "{percent}"));
+                let symbols = SymbolFmt::new(&options.format);
 
-                let timer = Timer::add_ms(50, clone!(label move || {
-                    if let Ok(pct) = r.try_recv() {
-                        label.set_markup(&symbols.format(|sym| match sym {
-                            "pct" => format!("{:?}%", pct as u32),
-                            _ => sym.to_string(),
-                        }));
-                    }
+                let listener = r.attach(clone!(label move |pct| {
+                    label.set_markup(&symbols.format(|sym| match sym {
+                        "pct" => format!("{:?}%", pct as u32),
+                        _ => sym.to_string(),
+                    }));
                     gtk::Continue(true)
                 }));
 
                 window.add_component(Box::new(Backlight {
                     label,
-                    timer,
-                    watcher: s_dead,
+                    listener,
+                    wakeup,
                 }));
 
             },
@@ -8,6 +8,16 @@ use std::rc::Rc;
 
 use probes::network;
 
+options! {
+    pub struct BandwidthOptions {
+        /// omit to show all
+        "interfaces" => interfaces: Vec<String> = vec![],
+        /// symbols are; name, down/s, up/s, down/total, up/total
+        "format" => format: String = "{down/s}",
+        "interval" => interval: i64 = 3,
+    }
+}
+
 pub struct Bandwidth {
     wrapper: gtk::Box,
     timer: Timer,
@@ -18,18 +28,23 @@ impl Component for Bandwidth {
         self.timer.remove();
         self.wrapper.destroy();
     }
+
+    fn refresh(&self) {
+        self.timer.tick();
+    }
 }
 
 impl Bandwidth {
     pub fn init(params: ComponentParams) {
         let ComponentParams { config, window, container, .. } = params;
+        let options = BandwidthOptions::from_config(&config);
 
         let label_group = LabelGroup::new();
         super::init_widget(&label_group.wrapper, &config, &window, container);
 
-        let interfaces = config.get_string_vec("interfaces");
-        let interval = config.get_int_or("interval", 3).max(1) as u64;
-        let symbols = SymbolFmt::new(config.get_str_or("format", "{down/s}"));
+        let interfaces = options.interfaces;
+        let interval = options.interval.max(1) as u64;
+        let symbols = SymbolFmt::new(&options.format);
 
         let should_include =
             move |s: &str| interfaces.len() == 0 || interfaces.contains(&&s.to_string());
@@ -4,6 +4,17 @@ use gtk::{Label, StyleContextExt};
 use std::io::Error;
 use crate::util::{read_file, SymbolFmt, Timer};
 
+options! {
+    pub struct BatteryOptions {
+        /// use `ls /sys/class/power_supply/` to see devices
+        "battery" => battery: String = "BAT0",
+        "adapter" => adapter: String = "AC",
+        /// symbols are; percent, remaining, plugged
+        "format" => format: String = "{percent}",
+        "interval" => interval: i64 = 3,
+    }
+}
+
 pub struct Battery {
     label: Label,
     timer: Timer,
@@ -14,20 +25,25 @@ impl Component for Battery {
         self.timer.remove();
         self.label.destroy();
     }
+
+    fn refresh(&self) {
+        self.timer.tick();
+    }
 }
 
 impl Battery {
     pub fn init(params: ComponentParams) {
         let ComponentParams { config, window, container, .. } = params;
+        let options = BatteryOptions::from_config(&config);
         let label = Label::new(None);
         super::init_widget(&label, &config, &window, container);
         label.show();
 
-        let adapter = config.get_str_or("adapter", "AC").to_string();
-        let battery = config.get_str_or("battery", "BAT0").to_string();
+        let adapter = options.adapter;
+        let battery = options.battery;
         let has_battery = get_data(&battery, "charge_full").is_ok();
 
-        let symbols = SymbolFmt::new(config.get_str_or("format", "{percent}"));
+        let symbols = SymbolFmt::new(&options.format);
 
         if has_battery {
             let tick = clone!(label move || {
@@ -75,7 +91,7 @@ impl Battery {
                 gtk::Continue(true)
             });
 
-            let interval = config.get_int_or("interval", 3).max(1);
+            let interval = options.interval.max(1);
             let timer = Timer::add_seconds(interval as u32, tick);
 
             window.add_component(Box::new(Battery {
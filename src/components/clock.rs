@@ -4,6 +4,16 @@ use gtk::prelude::*;
 use gtk::Label;
 use crate::util::{SymbolFmt, Timer};
 
+options! {
+    pub struct ClockOptions {
+        /// see the chrono strftime docs for formatting
+        "timestamp" => timestamp: String = "%Y-%m-%d %H:%M:%S",
+        /// symbols are; timestamp
+        "format" => format: String = "{timestamp}",
+        "interval" => interval: i64 = 1,
+    }
+}
+
 pub struct Clock {
     label: Label,
     timer: Timer,
@@ -14,21 +24,24 @@ impl Component for Clock {
         self.timer.remove();
         self.label.destroy();
     }
+
+    fn refresh(&self) {
+        self.timer.tick();
+    }
 }
 
 impl Clock {
     pub fn init(params: ComponentParams) {
         let ComponentParams { config, window, container, .. } = params;
+        let options = ClockOptions::from_config(&config);
         let label = Label::new(None);
         super::init_widget(&label, &config, &window, container);
         label.show();
 
         // get config
-        let symbols = SymbolFmt::new(config.get_str_or("format", "{timestamp}"));
-        let timestamp = config
-            .get_str_or("timestamp", "%Y-%m-%d %H:%M:%S")
-            .to_string();
-        let interval = config.get_int_or("interval", 1).max(1);
+        let symbols = SymbolFmt::new(&options.format);
+        let timestamp = options.timestamp;
+        let interval = options.interval.max(1);
 
         // start timer
         let tick = clone!(label move || {
@@ -14,6 +14,13 @@ mod autosuggest;
 
 use autosuggest::Suggestions;
 
+options! {
+    pub struct CommandInputOptions {
+        /// number of commands to remember
+        "history" => history: i64 = 1000,
+    }
+}
+
 pub struct CommandInput {
     config: ConfigGroup,
     wrapper: gtk::Box,
@@ -49,8 +56,9 @@ fn get_abs_rect(wrapper: &gtk::Box) -> Rectangle {
 impl CommandInput {
     pub fn init(params: ComponentParams) {
         let ComponentParams { config, window, container, wm_util } = params;
+        let options = CommandInputOptions::from_config(&config);
 
-        let history_limit = config.get_int_or("history", 1000) as usize;
+        let history_limit = options.history as usize;
 
         // create wrapper
 
@@ -2,6 +2,17 @@ use crate::components::{Component, ComponentParams};
 use gtk::prelude::*;
 use gtk::Orientation;
 
+options! {
+    pub struct ContainerOptions {
+        /// gap between components
+        "spacing" => spacing: i64 = 0,
+        /// horizontal or vertical
+        "direction" => direction: String = "horizontal",
+        /// components to add, identified by name
+        "layout" => layout: Vec<String> = vec![],
+    }
+}
+
 pub struct Container {
     wrapper: gtk::Box,
 }
@@ -15,11 +26,12 @@ impl Component for Container {
 impl Container {
     pub fn init(params: ComponentParams) {
         let ComponentParams { config, window, wm_util, container } = params;
+        let options = ContainerOptions::from_config(&config);
         // get spacing
-        let spacing = config.get_int_or("spacing", 0) as i32;
+        let spacing = options.spacing as i32;
 
         // get direction
-        let direction = match config.get_str_or("direction", "horizontal") {
+        let direction = match options.direction.as_str() {
             "horizontal" => Orientation::Horizontal,
             _ => Orientation::Vertical,
         };
@@ -29,7 +41,7 @@ impl Container {
         wrapper.show();
 
         // load layout
-        for name in config.get_string_vec("layout") {
+        for name in options.layout {
             let config_opt = wm_util.get_component_config(&name);
             if let Some(config) = config_opt {
                 window.load_component(config, &wrapper, &wm_util);
@@ -5,6 +5,14 @@ use crate::util::{read_file, SymbolFmt, Timer};
 
 use sysinfo::{ProcessorExt, System, SystemExt};
 
+options! {
+    pub struct CPUOptions {
+        /// symbols are; usage, temp, dumbtemp
+        "format" => format: String = "{usage}",
+        "interval" => interval: i64 = 3,
+    }
+}
+
 pub struct CPU {
     label: Label,
     timer: Timer,
@@ -15,17 +23,22 @@ impl Component for CPU {
         self.timer.remove();
         self.label.destroy();
     }
+
+    fn refresh(&self) {
+        self.timer.tick();
+    }
 }
 
 impl CPU {
     pub fn init(params: ComponentParams) {
         let ComponentParams { config, window, container, .. } = params;
+        let options = CPUOptions::from_config(&config);
         let label = Label::new(None);
         super::init_widget(&label, &config, &window, container);
         label.show();
 
         let mut system = System::new();
-        let symbols = SymbolFmt::new(config.get_str_or("format", "{usage}"));
+        let symbols = SymbolFmt::new(&options.format);
         let has_usage = symbols.contains("usage");
 
         let tick = clone!(label move || {
@@ -62,7 +75,7 @@ impl CPU {
             gtk::Continue(true)
         });
 
-        let interval = config.get_int_or("interval", 3).max(1);
+        let interval = options.interval.max(1);
         let timer = Timer::add_seconds(interval as u32, tick);
 
         window.add_component(Box::new(CPU {
@@ -4,6 +4,16 @@ use crate::util::{format_bytes, LabelGroup, SymbolFmt, Timer};
 
 use probes::disk_usage;
 
+options! {
+    pub struct DiskOptions {
+        /// omit to show all
+        "mounts" => mounts: Vec<String> = vec![],
+        /// symbols are; free, used, total, fs, mount
+        "format" => format: String = "{free}",
+        "interval" => interval: i64 = 3,
+    }
+}
+
 pub struct Disk {
     wrapper: gtk::Box,
     timer: Timer,
@@ -14,16 +24,21 @@ impl Component for Disk {
         self.timer.remove();
         self.wrapper.destroy();
     }
+
+    fn refresh(&self) {
+        self.timer.tick();
+    }
 }
 
 impl Disk {
     pub fn init(params: ComponentParams) {
         let ComponentParams { config, window, container, .. } = params;
+        let options = DiskOptions::from_config(&config);
         let label_group = LabelGroup::new();
         super::init_widget(&label_group.wrapper, &config, &window, container);
 
-        let mounts = config.get_string_vec("mounts");
-        let symbols = SymbolFmt::new(config.get_str_or("format", "{free}"));
+        let mounts = options.mounts;
+        let symbols = SymbolFmt::new(&options.format);
 
         let should_include = move |s: &str| mounts.len() == 0 || mounts.contains(&&s.to_string());
 
@@ -61,7 +76,7 @@ impl Disk {
             gtk::Continue(true)
         });
 
-        let interval = config.get_int_or("interval", 3).max(1);
+        let interval = options.interval.max(1);
         let timer = Timer::add_seconds(interval as u32, tick);
 
         window.add_component(Box::new(Disk {
@@ -1,10 +1,67 @@
 use crate::components::{Component, ComponentParams};
-use crate::config::Property;
+use crate::wm::WMUtil;
+use crate::config::{Property, PropertyType, Type};
+use serde::{Deserialize, Deserializer, Serialize};
 use gtk::prelude::*;
 use gtk::{EventBox, Label, Menu as GtkMenu, MenuItem as GtkMenuItem, WidgetExt};
 
 // gtk context menu
 
+options! {
+    pub struct DropdownOptions {
+        /// markup shown in the bar
+        "label" => label: String = "",
+        /// each item has a label and either a command or children
+        "items" => items: MenuItems = vec![],
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MenuItemOptions {
+    label: String,
+    #[serde(default)]
+    command: Option<String>,
+    #[serde(default)]
+    children: MenuItems,
+}
+
+/// items are read one at a time, so a bad one doesn't lose the rest
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(transparent)]
+pub struct MenuItems(Vec<MenuItemOptions>);
+
+impl From<Vec<MenuItemOptions>> for MenuItems {
+    fn from(items: Vec<MenuItemOptions>) -> Self {
+        MenuItems(items)
+    }
+}
+
+impl PropertyType for MenuItems {
+    const TYPE: Type = Type::Array;
+}
+
+impl<'de> Deserialize<'de> for MenuItems {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let items = Vec::<Property>::deserialize(deserializer)?;
+        Ok(MenuItems(items.into_iter().enumerate().filter_map(|(index, item)| {
+            match MenuItemOptions::deserialize(item) {
+                Ok(item) => {
+                    if item.command.is_none() && item.children.0.is_empty() {
+                        warn!("skipping menu item {:?} without a command or children", item.label);
+                        None
+                    } else {
+                        Some(item)
+                    }
+                },
+                Err(err) => {
+                    warn!("skipping menu item {}: {}", index + 1, err);
+                    None
+                },
+            }
+        }).collect()))
+    }
+}
+
 pub struct Dropdown {
     wrapper: EventBox,
 }
@@ -21,46 +78,28 @@ enum MenuItem {
     SubMenu(String, Vec<MenuItem>),
 }
 
-// TODO: move to config and refactor config (folders)
-fn get_menu(items: Vec<Property>) -> Vec<MenuItem> {
-    let mut menu_items: Vec<MenuItem> = Vec::new();
-    items.iter().for_each(|item| {
-        if let Property::Object(obj) = item {
-            let name_opt = obj.get("label");
-            let exec_opt = obj.get("command");
-            let child_opt = obj.get("children");
-            if let Some(Property::String(name)) = name_opt {
-                if let Some(Property::String(exec)) = exec_opt {
-                    let command = MenuItem::Command(name.clone(), exec.clone());
-                    menu_items.push(command);
-                } else if let Some(Property::Array(children)) = child_opt {
-                    let submenu = get_menu(children.to_vec());
-                    let children = MenuItem::SubMenu(name.clone(), submenu);
-                    menu_items.push(children);
-                }
-            }
-        }
-    });
-    menu_items
+fn get_menu(items: &MenuItems) -> Vec<MenuItem> {
+    items.0.iter().map(|item| match &item.command {
+        Some(command) => MenuItem::Command(item.label.clone(), command.clone()),
+        None => MenuItem::SubMenu(item.label.clone(), get_menu(&item.children)),
+    }).collect()
 }
 
 impl Dropdown {
     pub fn init(params: ComponentParams) {
-        let ComponentParams { config, window, container, .. } = params;
+        let ComponentParams { config, window, container, wm_util } = params;
+        let options = DropdownOptions::from_config(&config);
         let label = Label::new(None);
-        {
-            let label_text = config.get_str_or("label", "");
-            label.set_markup(&label_text);
-        } // config no longer borrowed
+        label.set_markup(&options.label);
 
         let ebox = EventBox::new();
         ebox.add(&label);
         ebox.show_all();
         super::init_widget(&ebox, &config, &window, container);
 
-        let menu_items = get_menu(config.get_vec_or("items", vec![]));
+        let menu_items = get_menu(&options.items);
 
-        let menu = Self::create_menu(&menu_items);
+        let menu = Self::create_menu(&menu_items, wm_util);
 
         menu.show_all();
 
@@ -73,18 +112,19 @@ impl Dropdown {
             wrapper: ebox,
         }));
     }
-    fn create_menu(menu_items: &Vec<MenuItem>) -> GtkMenu {
+    fn create_menu(menu_items: &Vec<MenuItem>, wm_util: &WMUtil) -> GtkMenu {
         let menu = GtkMenu::new();
         menu_items.iter().for_each(|item| match item {
             MenuItem::Command(label, command) => {
                 let item = GtkMenuItem::new_with_label(label);
                 menu.append(&item);
-                item.connect_activate(clone!(command move |_| {
-                        crate::util::run_command(command.to_string());
+                // prefix with : to run an IPC-style command
+                item.connect_activate(clone!((command, wm_util) move |_| {
+                        wm_util.run_command(&command);
                     }));
             }
             MenuItem::SubMenu(label, items) => {
-                let submenu = Self::create_menu(items);
+                let submenu = Self::create_menu(items, wm_util);
                 let item = GtkMenuItem::new_with_label(label);
                 item.set_submenu(&submenu);
                 menu.append(&item);
@@ -93,3 +133,36 @@ impl Dropdown {
         menu
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn item(properties: &[(&str, Property)]) -> Property {
+        Property::Object(properties.iter()
+            .map(|(key, value)| (key.to_string(), value.clone()))
+            .collect::<HashMap<String, Property>>())
+    }
+
+    fn string(value: &str) -> Property {
+        Property::String(value.to_string())
+    }
+
+    #[test]
+    fn invalid_items_are_skipped() {
+        let items = Property::Array(vec![
+            item(&[("label", string("lock")), ("command", string("slock"))]),
+            item(&[("label", Property::Integer(1)), ("command", string("reboot"))]),
+            item(&[("label", string("nothing"))]),
+            item(&[("label", string("power")), ("children", Property::Array(vec![
+                item(&[("label", string("off")), ("command", string("poweroff"))]),
+                item(&[("command", string("no label"))]),
+            ]))]),
+        ]);
+        let items = MenuItems::deserialize(items).unwrap();
+        let labels = items.0.iter().map(|item| item.label.as_str()).collect::<Vec<_>>();
+        assert_eq!(labels, vec!["lock", "power"]);
+        assert_eq!(items.0[1].children.0.len(), 1);
+    }
+}
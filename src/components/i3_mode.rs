@@ -7,6 +7,13 @@ use crate::util::SymbolFmt;
 use crate::wm::events::{Event, EventId, EventValue};
 use crate::wm::WMUtil;
 
+options! {
+    pub struct I3ModeOptions {
+        /// symbols are; mode
+        "format" => format: String = "{mode}",
+    }
+}
+
 pub struct I3Mode {
     wrapper: gtk::Box,
     event_id: EventId,
@@ -23,13 +30,14 @@ impl Component for I3Mode {
 impl I3Mode {
     pub fn init(params: ComponentParams) {
         let ComponentParams { config, window, container, wm_util } = params;
+        let options = I3ModeOptions::from_config(&config);
         let label = Label::new(None);
         let wrapper = gtk::Box::new(Orientation::Horizontal, 0);
         super::init_widget(&label, &config, &window, &wrapper);
         container.add(&wrapper);
         wrapper.show();
 
-        let symbols = SymbolFmt::new(config.get_str_or("format", "{mode}"));
+        let symbols = SymbolFmt::new(&options.format);
 
         let event_id = wm_util.add_listener(
             Event::Mode,
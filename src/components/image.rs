@@ -2,6 +2,13 @@ use crate::components::{Component, ComponentParams};
 use gtk::prelude::*;
 use gtk::Image as GtkImage;
 
+options! {
+    pub struct ImageOptions {
+        /// path to the image, relative to the file it's set in
+        "src" => src: Option<String> = None,
+    }
+}
+
 pub struct Image {
     image: GtkImage,
 }
@@ -15,9 +22,10 @@ impl Component for Image {
 impl Image {
 
     pub fn init(params: ComponentParams) {
-        let ComponentParams { config, window, container, wm_util } = params;
-        if let Some(src) = config.get_string("src") {
-            let img: GtkImage = GtkImage::new_from_file(&wm_util.get_path(&src));
+        let ComponentParams { config, window, container, .. } = params;
+        let options = ImageOptions::from_config(&config);
+        if let Some(src) = options.src {
+            let img: GtkImage = GtkImage::new_from_file(&config.get_path("src", &src));
             super::init_widget(&img, &config, &window, container);
 
             // wait a tick, otherwise we get negative height warnings
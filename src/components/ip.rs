@@ -5,6 +5,16 @@ use crate::util::{LabelGroup, SymbolFmt, Timer};
 use systemstat::data::{IpAddr, Network};
 use systemstat::{Platform, System};
 
+options! {
+    pub struct IPOptions {
+        /// omit to show all
+        "interfaces" => interfaces: Vec<String> = vec![],
+        /// symbols are; name, ipv4, ipv6
+        "format" => format: String = "{ipv4}",
+        "interval" => interval: i64 = 3,
+    }
+}
+
 pub struct IP {
     wrapper: gtk::Box,
     timer: Timer,
@@ -15,20 +25,25 @@ impl Component for IP {
         self.timer.remove();
         self.wrapper.destroy();
     }
+
+    fn refresh(&self) {
+        self.timer.tick();
+    }
 }
 
 impl IP {
     pub fn init(params: ComponentParams) {
         let ComponentParams { config, window, container, .. } = params;
+        let options = IPOptions::from_config(&config);
         let label_group = LabelGroup::new();
         super::init_widget(&label_group.wrapper, &config, &window, container);
 
-        let interfaces = config.get_string_vec("interfaces");
+        let interfaces = options.interfaces;
 
         let should_include =
             move |s: &str| interfaces.len() == 0 || interfaces.contains(&&s.to_string());
 
-        let symbols = SymbolFmt::new(config.get_str_or("format", "{ipv4}"));
+        let symbols = SymbolFmt::new(&options.format);
 
         let sys = System::new();
 
@@ -61,7 +76,7 @@ impl IP {
             gtk::Continue(true)
         });
 
-        let interval = config.get_int_or("interval", 3).max(1);
+        let interval = options.interval.max(1);
         let timer = Timer::add_seconds(interval as u32, tick);
 
         window.add_component(Box::new(IP {
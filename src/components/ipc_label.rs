@@ -0,0 +1,112 @@
+use crate::components::{Component, ComponentParams};
+use glib::source::SourceId;
+use gtk::prelude::*;
+use gtk::Label;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::wm::events::{Event, EventId, EventValue};
+use crate::wm::WMUtil;
+
+options! {
+    pub struct IPCLabelOptions {
+        /// markup shown until something is set
+        "default" => default: String = "",
+        /// seconds after a set before going back to the default. 0 never expires
+        "expire" => expire: i64 = 0,
+    }
+}
+
+pub struct IPCLabel {
+    label: Label,
+    name: String,
+    event_id: EventId,
+    wm_util: WMUtil,
+    expiry: Rc<RefCell<Option<SourceId>>>,
+}
+
+impl Component for IPCLabel {
+    fn destroy(&self) {
+        self.wm_util.remove_listener(Event::Set(self.name.clone()), self.event_id);
+        if let Some(id) = self.expiry.borrow_mut().take() {
+            glib::source_remove(id);
+        }
+        self.label.destroy();
+    }
+}
+
+impl IPCLabel {
+    pub fn init(params: ComponentParams) {
+        let ComponentParams { config, window, wm_util, container } = params;
+        let IPCLabelOptions { default, expire } = IPCLabelOptions::from_config(&config);
+        let label = Label::new(None);
+        super::init_widget(&label, &config, &window, container);
+        label.set_markup(&default);
+        label.show();
+
+        // classes added by set, so they can be replaced
+        let classes: Rc<RefCell<Vec<String>>> = Rc::new(RefCell::new(Vec::new()));
+        let expiry: Rc<RefCell<Option<SourceId>>> = Rc::new(RefCell::new(None));
+
+        let set_classes = Rc::new(clone!((label, classes) move |new_classes: Vec<String>| {
+            if let Some(ctx) = label.get_style_context() {
+                for class in classes.borrow().iter() {
+                    ctx.remove_class(class);
+                }
+                for class in new_classes.iter() {
+                    ctx.add_class(class);
+                }
+            }
+            *classes.borrow_mut() = new_classes;
+        }));
+
+        let reset = Rc::new(clone!((label, set_classes) move || {
+            label.set_markup(&default);
+            label.set_tooltip_markup(None::<&str>);
+            set_classes(Vec::new());
+        }));
+
+        let event_id = wm_util.add_listener(Event::Set(config.name.clone()),
+            clone!((label, expiry, reset) move |event_opt| {
+                if let Some(EventValue::Content { markup, tooltip, classes }) = event_opt {
+                    // only a set restarts the expiry, since that's what it resets
+                    if let Some(markup) = markup {
+                        if let Some(id) = expiry.borrow_mut().take() {
+                            glib::source_remove(id);
+                        }
+                        if markup.is_empty() {
+                            reset();
+                        } else {
+                            label.set_markup(&markup);
+                            set_classes(classes);
+                            if expire > 0 {
+                                let id = gtk::timeout_add_seconds(expire as u32,
+                                    clone!((expiry, reset) move || {
+                                        expiry.borrow_mut().take();
+                                        reset();
+                                        gtk::Continue(false)
+                                    }));
+                                *expiry.borrow_mut() = Some(id);
+                            }
+                        }
+                    }
+                    if let Some(tooltip) = tooltip {
+                        if tooltip.is_empty() {
+                            label.set_tooltip_markup(None::<&str>);
+                        } else {
+                            label.set_tooltip_markup(Some(tooltip.as_str()));
+                        }
+                    }
+                }
+            }));
+
+        window.add_component(Box::new(IPCLabel {
+            label,
+            name: config.name.clone(),
+            event_id,
+            wm_util: wm_util.clone(),
+            expiry,
+        }));
+    }
+}
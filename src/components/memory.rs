@@ -5,6 +5,14 @@ use crate::util::{format_bytes, SymbolFmt, Timer};
 
 use probes::memory;
 
+options! {
+    pub struct MemoryOptions {
+        /// symbols are; total, free, free-pct, used, used-pct, swap-total, swap-used
+        "format" => format: String = "{free-pct}",
+        "interval" => interval: i64 = 3,
+    }
+}
+
 pub struct Memory {
     label: Label,
     timer: Timer,
@@ -15,16 +23,21 @@ impl Component for Memory {
         self.timer.remove();
         self.label.destroy();
     }
+
+    fn refresh(&self) {
+        self.timer.tick();
+    }
 }
 
 impl Memory {
     pub fn init(params: ComponentParams) {
         let ComponentParams { config, window, container, .. } = params;
+        let options = MemoryOptions::from_config(&config);
         let label = Label::new(None);
         super::init_widget(&label, &config, &window, container);
         label.show();
 
-        let symbols = SymbolFmt::new(config.get_str_or("format", "{free-pct}"));
+        let symbols = SymbolFmt::new(&options.format);
 
         let name = config.name.clone();
         let tick = clone!(label move || {
@@ -56,7 +69,7 @@ impl Memory {
             gtk::Continue(true)
         });
 
-        let interval = config.get_int_or("interval", 3).max(1);
+        let interval = options.interval.max(1);
         let timer = Timer::add_seconds(interval as u32, tick);
 
         window.add_component(Box::new(Memory {
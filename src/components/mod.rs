@@ -1,4 +1,4 @@
-use crate::config::ConfigGroup;
+use crate::config::{ConfigGroup, Schema};
 use crate::wm;
 use gtk::{Align, ContainerExt, OverlayExt, StyleContextExt, WidgetExt};
 
@@ -13,6 +13,7 @@ mod disk;
 mod dropdown;
 mod i3_mode;
 mod image;
+mod ipc_label;
 mod ip;
 mod memory;
 mod script;
@@ -24,6 +25,8 @@ mod workspaces;
 pub trait Component {
     /// clean up any remaining timeouts, callbacks
     fn destroy(&self);
+    /// update now instead of waiting for the next interval
+    fn refresh(&self) {}
 }
 
 pub struct ComponentParams<'a> {
@@ -33,32 +36,84 @@ pub struct ComponentParams<'a> {
     pub wm_util: &'a wm::WMUtil,
 }
 
+options! {
+    /// properties shared by every component
+    pub struct CommonOptions {
+        /// the only required property
+        "type" => type_: String = "void",
+        /// inherit properties from another component
+        "extends" => extends: Option<String> = None,
+        "class" => class: Option<String> = None,
+        /// start | end | center | fill
+        "halign" => halign: Option<String> = None,
+        /// start | end | center | fill
+        "valign" => valign: Option<String> = None,
+        /// position absolutely instead of relatively
+        "fixed" => fixed: bool = false,
+        /// let fixed components ignore mouse events
+        "pass-through" => pass_through: bool = true,
+    }
+}
+
+macro_rules! component_types {
+    ( $( $type_:literal => $module:ident::$component:ident ),* $(,)? ) => {
+        fn get_init(type_: &str) -> Option<fn(ComponentParams)> {
+            match type_ {
+                $( $type_ => Some($module::$component::init), )*
+                _ => None,
+            }
+        }
+
+        /// every option of a component, with defaults filled in
+        pub fn get_resolved(config: &ConfigGroup) -> toml::value::Table {
+            let mut table = get_table(config);
+            match config.get_str_or("type", "void") {
+                $( $type_ => table.extend($module::get_table(config)), )*
+                _ => {},
+            }
+            table
+        }
+
+        /// get the properties accepted by a component type
+        pub fn get_properties(type_: &str) -> Option<Schema> {
+            match type_ {
+                $( $type_ => Some($module::PROPERTIES), )*
+                _ => None,
+            }
+        }
+    };
+}
+
+component_types!(
+    "backlight" => backlight::Backlight,
+    "bandwidth" => bandwidth::Bandwidth,
+    "battery" => battery::Battery,
+    "clock" => clock::Clock,
+    "command-input" => command_input::CommandInput,
+    "container" => container::Container,
+    "cpu" => cpu::CPU,
+    "disk" => disk::Disk,
+    "dropdown" => dropdown::Dropdown,
+    "i3-mode" => i3_mode::I3Mode,
+    "image" => image::Image,
+    "ip" => ip::IP,
+    "ipc-label" => ipc_label::IPCLabel,
+    "memory" => memory::Memory,
+    "script" => script::Script,
+    "tray" => tray::Tray,
+    "window-title" => window_title::WindowTitle,
+    "workspaces" => workspaces::Workspaces,
+);
+
 /// each component should call window.add_component
 pub fn load_component(params: ComponentParams) {
-    fn void(params: ComponentParams) {
-        warn!("a valid type is required for #{}", params.config.name)
-    }
     // decide which component to load
-    (match params.config.get_str_or("type", "void") {
-        "backlight" => backlight::Backlight::init,
-        "bandwidth" => bandwidth::Bandwidth::init,
-        "battery" => battery::Battery::init,
-        "clock" => clock::Clock::init,
-        "command-input" => command_input::CommandInput::init,
-        "container" => container::Container::init,
-        "cpu" => cpu::CPU::init,
-        "disk" => disk::Disk::init,
-        "dropdown" => dropdown::Dropdown::init,
-        "i3-mode" => i3_mode::I3Mode::init,
-        "image" => image::Image::init,
-        "ip" => ip::IP::init,
-        "memory" => memory::Memory::init,
-        "script" => script::Script::init,
-        "tray" => tray::Tray::init,
-        "window-title" => window_title::WindowTitle::init,
-        "workspaces" => workspaces::Workspaces::init,
-        _ => void,
-    })(params);
+    match get_init(params.config.get_str_or("type", "void")) {
+        Some(init) => init(params),
+        None => {
+            warn!("a valid type is required for #{}", params.config.name);
+        },
+    }
 }
 
 pub fn init_widget<'a, T>(
@@ -77,25 +132,23 @@ pub fn init_widget<'a, T>(
     // let widget = &wrapper;
     // set name
     widget.set_name(&config.name);
+    let options = CommonOptions::from_config(config);
     // class
-    let class_str = config.get_str_or("class", "void");
-    if class_str != "void" {
+    if let Some(class_str) = options.class {
         if let Some(ctx) = widget.get_style_context() {
-            ctx.add_class(class_str);
+            ctx.add_class(&class_str);
         }
     }
-    let is_fixed = config.get_bool_or("fixed", false);
+    let is_fixed = options.fixed;
     // set alignment
-    let halign_str = config.get_str_or("halign", "void");
-    if halign_str != "void" {
-        WidgetExt::set_halign(widget, get_alignment(halign_str));
+    if let Some(halign_str) = options.halign {
+        WidgetExt::set_halign(widget, get_alignment(&halign_str));
         if !is_fixed {
             WidgetExt::set_hexpand(widget, true);
         }
     }
-    let valign_str = config.get_str_or("valign", "void");
-    if valign_str != "void" {
-        WidgetExt::set_valign(widget, get_alignment(valign_str));
+    if let Some(valign_str) = options.valign {
+        WidgetExt::set_valign(widget, get_alignment(&valign_str));
         if !is_fixed {
             WidgetExt::set_vexpand(widget, true);
         }
@@ -104,7 +157,7 @@ pub fn init_widget<'a, T>(
     if is_fixed {
         let overlay = window.get_overlay();
         overlay.add_overlay(widget);
-        if config.get_bool_or("pass-through", true) {
+        if options.pass_through {
             overlay.set_overlay_pass_through(widget, true);
         }
     } else {
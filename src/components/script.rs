@@ -3,47 +3,64 @@ use gtk::prelude::*;
 use gtk::Label;
 use std::io::Error;
 use std::process::{Command, Stdio};
-use std::sync::mpsc;
+use std::sync::mpsc::{self, RecvTimeoutError};
 use std::thread;
 use std::time::Duration;
-use crate::util::{SymbolFmt, Timer};
+use crate::util::{self, Listener, SymbolFmt};
+
+options! {
+    pub struct ScriptOptions {
+        /// shell script to run
+        "src" => src: Option<String> = None,
+        /// symbols are; stdout, stderr, code
+        "format" => format: String = "{stdout}",
+        "interval" => interval: i64 = 3,
+    }
+}
 
 pub struct Script {
     label: Label,
-    timer: Timer,
-    tx_term: mpsc::Sender<()>,
+    listener: Listener,
+    tx_msg: mpsc::Sender<ScriptMsg>,
+}
+
+enum ScriptMsg {
+    Refresh,
+    Term,
 }
 
 impl Component for Script {
     fn destroy(&self) {
-        self.timer.remove();
-        self.tx_term.send(()).ok();
+        self.listener.remove();
+        self.tx_msg.send(ScriptMsg::Term).ok();
         self.label.destroy();
     }
+
+    fn refresh(&self) {
+        self.tx_msg.send(ScriptMsg::Refresh).ok();
+    }
 }
 
 impl Script {
     pub fn init(params: ComponentParams) {
         let ComponentParams { config, window, container, .. } = params;
-        if let Some(src) = config.get_string("src") {
-            let (tx, rx) = mpsc::channel();
-            let (tx_term, rx_term) = mpsc::channel();
-
-            let interval = config.get_int_or("interval", 3).max(1);
-            let symbols = SymbolFmt::new(config.get_str_or("format", "{stdout}"));
+        let options = ScriptOptions::from_config(&config);
+        if let Some(src) = options.src {
+            let (tx, rx) = util::channel();
+            let (tx_msg, rx_msg) = mpsc::channel();
 
-            if let Ok(output) = get_output(&src) {
-                tx.send(output).ok();
-            }
+            let interval = options.interval.max(1);
+            let symbols = SymbolFmt::new(&options.format);
 
             thread::spawn(clone!((src, interval) move || {
                 loop {
-                    thread::sleep(Duration::from_secs(interval as u64));
                     if let Ok(output) = get_output(&src) {
                         tx.send(output).ok();
                     }
-                    if let Ok(_) = rx_term.try_recv() {
-                        break;
+                    // run again after the interval, or now if refreshed
+                    match rx_msg.recv_timeout(Duration::from_secs(interval as u64)) {
+                        Ok(ScriptMsg::Refresh) | Err(RecvTimeoutError::Timeout) => {},
+                        Ok(ScriptMsg::Term) | Err(RecvTimeoutError::Disconnected) => break,
                     }
                 }
             }));
@@ -52,25 +69,22 @@ impl Script {
             super::init_widget(&label, &config, &window, container);
             label.show();
 
-            let tick = clone!(label move || {
-                if let Ok((ref stdout, ref stderr, code)) = rx.try_recv() {
-                    label.set_markup(&symbols.format(|sym| {
-                        match sym {
-                            "stdout" => stdout.to_string(),
-                            "stderr" => stderr.to_string(),
-                            "code" => code.to_string(),
-                            _ => sym.to_string(),
-                        }
-                    }));
-                }
+            let listener = rx.attach(clone!(label move |(stdout, stderr, code)| {
+                label.set_markup(&symbols.format(|sym| {
+                    match sym {
+                        "stdout" => stdout.to_string(),
+                        "stderr" => stderr.to_string(),
+                        "code" => code.to_string(),
+                        _ => sym.to_string(),
+                    }
+                }));
                 gtk::Continue(true)
-            });
-            let timer = Timer::add_seconds(interval as u32, tick);
+            }));
 
             window.add_component(Box::new(Script {
                 label,
-                timer,
-                tx_term,
+                listener,
+                tx_msg,
             }));
         } else {
             warn!("src property missing from #{}", config.name);
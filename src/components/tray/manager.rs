@@ -1,6 +1,7 @@
 use super::Action;
-use crossbeam_channel::Sender;
+use crate::util::Sender;
 use crate::wm::atom;
+use crate::wm::xcb::Subscription;
 use xcb;
 
 const CLIENT_MESSAGE: u8 = xcb::CLIENT_MESSAGE | 0x80; // 0x80 flag for client messages
@@ -12,8 +13,9 @@ const SYSTEM_TRAY_CANCEL_MESSAGE: u32 = 2;
 
 pub struct Manager<'a> {
     conn: &'a xcb::Connection,
-    atoms: &'a atom::Atoms<'a>,
+    atoms: &'a atom::Atoms,
     screen: &'a xcb::Screen<'a>,
+    events: &'a Subscription,
     icon_size: u16,
     icon_spacing: u16,
     window: xcb::Window,
@@ -29,12 +31,14 @@ impl<'a> Manager<'a> {
         conn: &'b xcb::Connection,
         atoms: &'b atom::Atoms,
         screen: &'b xcb::Screen,
+        events: &'b Subscription,
         s_tray: Sender<Action>,
     ) -> Manager<'b> {
         Manager::<'b> {
             conn: conn,
             atoms: atoms,
             screen: screen,
+            events: events,
             icon_size: 20,
             icon_spacing: 0,
             window: conn.generate_id(),
@@ -61,10 +65,10 @@ impl<'a> Manager<'a> {
             self.screen.root_visual(),
             &[
                 (xcb::CW_BACK_PIXEL, 0), // black
-                (xcb::CW_EVENT_MASK, xcb::EVENT_MASK_PROPERTY_CHANGE),
                 (xcb::CW_OVERRIDE_REDIRECT, 1),
             ],
         );
+        self.events.watch(self.window, xcb::EVENT_MASK_PROPERTY_CHANGE);
         self.set_property(xcb::ATOM_WM_NAME, xcb::ATOM_STRING, 8, WM_NAME.as_bytes());
         self.set_property(
             xcb::ATOM_WM_CLASS,
@@ -255,11 +259,7 @@ impl<'a> Manager<'a> {
     pub fn adopt(&mut self, window: xcb::Window) {
         let spacing = (self.children.len() as u16 * self.icon_spacing) as i16;
         let offset = spacing + (self.children.len() as u16 * self.icon_size) as i16;
-        xcb::change_window_attributes(
-            self.conn,
-            window,
-            &[(xcb::CW_EVENT_MASK, xcb::EVENT_MASK_STRUCTURE_NOTIFY)],
-        );
+        self.events.watch(window, xcb::EVENT_MASK_STRUCTURE_NOTIFY);
         xcb::reparent_window(self.conn, window, self.window, offset, 0);
         xcb::map_window(self.conn, window);
         self.force_size(window, None);
@@ -327,19 +327,11 @@ impl<'a> Manager<'a> {
 
         for child in self.children.iter() {
             let window = *child;
-            xcb::change_window_attributes(
-                self.conn,
-                window,
-                &[(xcb::CW_EVENT_MASK, xcb::EVENT_MASK_NO_EVENT)],
-            );
+            self.events.unwatch(window);
             xcb::unmap_window(self.conn, window);
             xcb::reparent_window(self.conn, window, root, 0, 0);
         }
-        xcb::change_window_attributes(
-            self.conn,
-            self.window,
-            &[(xcb::CW_EVENT_MASK, xcb::EVENT_MASK_STRUCTURE_NOTIFY)],
-        );
+        self.events.watch(self.window, xcb::EVENT_MASK_STRUCTURE_NOTIFY);
         xcb::destroy_window(self.conn, self.window);
         self.conn.flush();
     }
@@ -4,15 +4,13 @@ use glib::translate::ToGlib;
 use glib_sys::g_source_remove;
 use gtk::prelude::*;
 use gtk::Orientation;
-use crate::util::Timer;
+use crate::util::{self, Listener};
 
 use crossbeam_channel::{self as channel, select};
 use glib;
-use std::sync::Arc;
 use std::time::Duration;
 use std::{process, thread};
 use crate::wm;
-use xcb;
 
 mod manager;
 
@@ -33,16 +31,23 @@ pub enum Action {
     Quit,
 }
 
+options! {
+    pub struct TrayOptions {
+        "icon-size" => icon_size: i64 = 20,
+        "icon-spacing" => icon_spacing: i64 = 0,
+    }
+}
+
 pub struct Tray {
     base_widget: gtk::Box,
-    timer: Timer,
+    listener: Listener,
     sender: channel::Sender<Action>,
 }
 
 impl Component for Tray {
     fn destroy(&self) {
         self.base_widget.destroy();
-        self.timer.remove();
+        self.listener.remove();
         if let Err(err) = self.sender.send(Action::Quit) {
             error!("{}", err);
         }
@@ -65,6 +70,7 @@ impl Tray {
     }
     pub fn be_a_tray(params: ComponentParams) {
         let ComponentParams { config, window, container, .. } = params;
+        let options = TrayOptions::from_config(&config);
         // extra surrounding base widget added for margins, etc
         let wrapper = gtk::Box::new(Orientation::Horizontal, 0);
         let base_widget = gtk::Box::new(Orientation::Horizontal, 0);
@@ -74,7 +80,7 @@ impl Tray {
 
         // communication
         let (s_main, r_main) = channel::unbounded();
-        let (s_tray, r_tray) = channel::unbounded();
+        let (s_tray, r_tray) = util::channel();
 
         // UI events/data
 
@@ -91,11 +97,11 @@ impl Tray {
         }
 
         // set icon size/spacing
-        let icon_size = config.get_int_or("icon-size", 20);
+        let icon_size = options.icon_size;
         if icon_size != 20 {
             s_main.send(Action::IconSize(icon_size as u16)).unwrap();
         }
-        let icon_spacing = config.get_int_or("icon-spacing", 0);
+        let icon_spacing = options.icon_spacing;
         if icon_spacing != 0 {
             s_main.send(Action::IconSpacing(icon_spacing as u16)).unwrap();
         }
@@ -115,14 +121,11 @@ impl Tray {
 
         // start tray context
         thread::spawn(move || {
-            if let Ok((conn, screen_num)) = xcb::Connection::connect(None) {
-                let conn = Arc::new(conn);
-                let atoms = wm::atom::Atoms::new(&conn);
-                let screen_num = screen_num as usize;
-                let setup = conn.get_setup();
-                let screen = setup.roots().nth(screen_num).unwrap();
+            if let Ok(conn) = wm::xcb::get_connection() {
+                let screen = conn.get_screen();
+                let events = wm::xcb::Subscription::new(&conn);
 
-                let mut manager = manager::Manager::new(&conn, &atoms, &screen, s_tray);
+                let mut manager = manager::Manager::new(&conn, conn.atoms(), &screen, &events, s_tray);
 
                 if !manager.is_selection_available() {
                     warn!("another system tray is already running");
@@ -131,26 +134,20 @@ impl Tray {
 
                 manager.create();
 
-                let (s_events, r_events) = channel::unbounded();
-                thread::spawn(clone!(conn move || {
-                    while let Some(event) = conn.wait_for_event() {
-                        s_events.send(event).ok();
-                    }
-                }));
-
                 let (r_signals, signal_destroy) = Tray::get_signals();
 
                 loop {
                     select! {
                         // xcb events
-                        recv(r_events) -> event_opt => {
+                        recv(events.get_receiver()) -> event_opt => {
                             if let Ok(event) = event_opt {
                                 if let Some(code) = manager.handle_event(event) {
                                     info!("system tray exited with code {}", code);
                                     return;
                                 }
                             } else {
-                                error!("uhoh");
+                                error!("tray: lost connection to X server");
+                                return;
                             }
                         },
                         // gtk events
@@ -167,7 +164,7 @@ impl Tray {
                         },
                         // fullscreen tick
                         recv(fullscreen_tick) -> _ => {
-                            if wm::xcb::check_fullscreen(&conn, &atoms, &screen) {
+                            if wm::xcb::check_fullscreen(&conn) {
                                 manager.hide();
                             } else {
                                 manager.show();
@@ -187,24 +184,22 @@ impl Tray {
         });
 
         // receive events
-        let timer = Timer::add_ms(10, clone!(base_widget move || {
-            if let Ok(msg) = r_tray.try_recv() {
-                match msg {
-                    Action::Width(w) => {
-                        wrapper.set_size_request(w as i32, icon_size as i32);
-                        // the next lines fix a background display bug
-                        base_widget.hide();
-                        base_widget.show();
-                    },
-                    _ => {},
-                }
+        let listener = r_tray.attach(clone!(base_widget move |msg| {
+            match msg {
+                Action::Width(w) => {
+                    wrapper.set_size_request(w as i32, icon_size as i32);
+                    // the next lines fix a background display bug
+                    base_widget.hide();
+                    base_widget.show();
+                },
+                _ => {},
             }
             gtk::Continue(true)
         }));
 
         window.add_component(Box::new(Tray {
             base_widget,
-            timer,
+            listener,
             sender: s_main,
         }));
     }
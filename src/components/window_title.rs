@@ -8,6 +8,15 @@ use crate::util::SymbolFmt;
 use crate::wm::events::{Event, EventId, EventValue};
 use crate::wm::WMUtil;
 
+options! {
+    pub struct WindowTitleOptions {
+        /// symbols are; title
+        "format" => format: String = "{title}",
+        /// maximum number of characters to show
+        "truncate" => truncate: i64 = 100,
+    }
+}
+
 pub struct WindowTitle {
     label: Label,
     event_id: EventId,
@@ -24,12 +33,13 @@ impl Component for WindowTitle {
 impl WindowTitle {
     pub fn init(params: ComponentParams) {
         let ComponentParams { config, window, wm_util, container } = params;
+        let options = WindowTitleOptions::from_config(&config);
         let label = Label::new(None);
         super::init_widget(&label, &config, &window, container);
         label.show();
 
-        let trunc = config.get_int_or("truncate", 100) as usize;
-        let symbols = SymbolFmt::new(config.get_str_or("format", "{title}"));
+        let trunc = options.truncate as usize;
+        let symbols = SymbolFmt::new(&options.format);
 
         let event_id = wm_util.add_listener(Event::WindowTitle, clone!(label
             move |event_opt| {
@@ -14,6 +14,17 @@ use std::cell::RefCell;
 use std::mem::replace;
 use std::rc::Rc;
 
+options! {
+    pub struct WorkspacesOptions {
+        /// show workspaces from every monitor
+        "show-all" => show_all: bool = false,
+        /// gap between items
+        "spacing" => spacing: i64 = 0,
+        /// symbols are; number, name
+        "format" => format: String = "{number}",
+    }
+}
+
 pub struct Workspaces {
     wrapper: gtk::Box,
     event_id: EventId,
@@ -30,14 +41,15 @@ impl Component for Workspaces {
 impl Workspaces {
     pub fn init(params: ComponentParams) {
         let ComponentParams { config, window, container, wm_util } = params;
+        let options = WorkspacesOptions::from_config(&config);
         let monitor_index = window.get_monitor_index() as i32;
 
         // get spacing
-        let spacing = config.get_int_or("spacing", 0) as i32;
+        let spacing = options.spacing as i32;
 
         // misc config
-        let show_all = config.get_bool_or("show-all", false);
-        let symbols = SymbolFmt::new(config.get_str_or("format", "{number}"));
+        let show_all = options.show_all;
+        let symbols = SymbolFmt::new(&options.format);
 
         // attach wrapper
         let wrapper = gtk::Box::new(Orientation::Horizontal, spacing);
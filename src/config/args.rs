@@ -10,12 +10,44 @@ pub struct Args {
     pub watch: bool,
     #[options(help = "Send an IPC message", meta = "[MESSAGE]")]
     pub message: Option<String>,
+    #[options(help = "Send the IPC message as JSON and print the reply")]
+    pub json: bool,
+    #[options(help = "Name this instance, or the instance to message", meta = "[NAME]", short = "I")]
+    pub instance: Option<String>,
+    #[options(help = "Send the IPC message to every instance")]
+    pub all: bool,
+    #[options(help = "List running instances, then exit", no_short)]
+    pub list_instances: bool,
     #[options(help = "Shows information about monitors")]
     pub monitors: bool,
+    #[options(help = "Check the config and theme for errors, then exit")]
+    pub check: bool,
+    #[options(help = "Create a starter config and theme, then exit")]
+    pub init: bool,
+    #[options(help = "Print the resolved config as toml or json, then exit", meta = "[FORMAT]")]
+    pub print_config: Option<String>,
     #[options(short = "D", no_long)]
     pub multi: bool,
 }
 
+impl Args {
+    pub fn get_config_path(&self) -> String {
+        match &self.config {
+            Some(path) => path.to_string(),
+            None => format!("{}/config.toml", *super::CONFIG_DIR),
+        }
+    }
+
+    pub fn get_instance(&self) -> &str {
+        self.instance.as_ref().map_or("default", String::as_str)
+    }
+}
+
+/// letters, digits, `_` and `-`
+pub fn is_valid_instance(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
 pub fn get_args() -> Args {
     let args: Vec<String> = env::args().collect();
 
@@ -25,6 +57,11 @@ pub fn get_args() -> Args {
                 println!("{} {}\n", crate::NAME, crate::VERSION);
                 Args::parse_args_default_or_exit();
             }
+            // instance names are used as filenames and D-Bus names
+            if !is_valid_instance(args.get_instance()) {
+                error!("invalid instance name {:?}", args.get_instance());
+                std::process::exit(1);
+            }
             args
         },
         Err(err) => {
@@ -33,3 +70,18 @@ pub fn get_args() -> Args {
         },
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instance_names() {
+        for name in ["default", "dock", "HDMI_1", "2nd-bar"].iter() {
+            assert!(is_valid_instance(name), "{:?} should be valid", name);
+        }
+        for name in ["", ".", "..", "a/b", "two words", " dock", "dock\n", "bär"].iter() {
+            assert!(!is_valid_instance(name), "{:?} should be invalid", name);
+        }
+    }
+}
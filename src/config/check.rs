@@ -0,0 +1,99 @@
+use crate::config::{parse_file, Config};
+use crate::wm;
+
+/// report problems with a config and its theme without starting the bar
+pub fn check(config_path: &str) -> bool {
+    let (config, mut errors) = match get_config_errors(config_path) {
+        Ok(result) => result,
+        Err(err) => {
+            error!("{}", err);
+            return false;
+        },
+    };
+
+    // CssProvider needs GTK to be initialized
+    if gtk::init().is_ok() {
+        let theme = config.get_theme();
+        if theme.is_empty() {
+            errors.push(format!(
+                "{}: theme could not be found",
+                config.get_filename(),
+            ));
+        } else {
+            errors.extend(wm::gtk::get_theme_errors(&theme));
+        }
+    } else {
+        warn!("failed to initialize GTK, skipping theme");
+    }
+
+    for err in errors.iter() {
+        error!("{}", err);
+    }
+
+    if errors.is_empty() {
+        info!("{} is valid", config_path);
+    } else {
+        error!("found {} problem(s) in {}", errors.len(), config_path);
+    }
+
+    errors.is_empty()
+}
+
+/// parse a config and check it against the schemas
+fn get_config_errors(config_path: &str) -> Result<(Config, Vec<String>), String> {
+    let config = parse_file(config_path)?;
+    let errors = config.validate()
+        .iter()
+        .map(|err| err.to_string())
+        .collect();
+    Ok((config, errors))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::write_test_files;
+
+    fn get_errors(name: &str, contents: &str) -> Result<Vec<String>, String> {
+        let dir = write_test_files(name, &[("config.toml", contents)]);
+        let path = dir.join("config.toml").to_string_lossy().to_string();
+        get_config_errors(&path).map(|(_, errors)| errors)
+    }
+
+    #[test]
+    fn valid_config() {
+        let errors = get_errors("check-valid", r#"
+            enable-ipc = true
+            [bar.main]
+            layout = ["clock"]
+            [component.clock]
+            type = "clock"
+            interval = 5
+        "#);
+        assert_eq!(errors, Ok(vec![]));
+    }
+
+    #[test]
+    fn invalid_config() {
+        let errors = get_errors("check-invalid", r#"
+            [bar.main]
+            layout = ["clock"]
+            [component.clock]
+            type = "clock"
+            interval = "5"
+        "#).unwrap();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].ends_with(
+            "config.toml:6:13: interval of component #clock should be an integer, found a string"
+        ), "{}", errors[0]);
+    }
+
+    #[test]
+    fn unparseable_config() {
+        let dir = write_test_files("check-unparseable", &[("config.toml", "[bar.main")]);
+        let path = dir.join("config.toml").to_string_lossy().to_string();
+        assert!(get_config_errors(&path).is_err());
+        // fails before GTK is needed for the theme
+        assert!(!check(&path));
+    }
+}
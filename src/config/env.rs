@@ -10,12 +10,29 @@ fn get_xdg(path: &str, default: &str) -> String {
     }
 }
 
+fn get_runtime_dir() -> String {
+    if let Ok(runtime_path) = env::var("XDG_RUNTIME_DIR") {
+        format!("{}/{}", runtime_path, crate::NAME)
+    } else {
+        format!("/tmp/{}-{}", crate::NAME, unsafe { libc::getuid() })
+    }
+}
+
+/// the IPC socket for a named instance
+pub fn get_socket_path(instance: &str) -> String {
+    match &*CAKEYBAR_SOCKET {
+        Some(path) => path.to_string(),
+        None => format!("{}/{}.sock", *SOCKET_DIR, instance),
+    }
+}
+
 lazy_static! {
     pub static ref NO_COLOR: bool = env::var("NO_COLOR").is_ok();
     pub static ref BSPWM_SOCKET: String = env::var("BSPWM_SOCKET")
         .unwrap_or_else(|_| "/tmp/bspwm_0_0-socket".to_string());
-    pub static ref CAKEYBAR_SOCKET: String = env::var("CAKEYBAR_SOCKET")
-        .unwrap_or_else(|_| "/tmp/cakeybar".to_string());
+    /// overrides the socket path of every instance
+    pub static ref CAKEYBAR_SOCKET: Option<String> = env::var("CAKEYBAR_SOCKET").ok();
+    pub static ref SOCKET_DIR: String = get_runtime_dir();
     pub static ref CONFIG_DIR: String = get_xdg("XDG_CONFIG_HOME", ".config");
     pub static ref CACHE_DIR: String = get_xdg("XDG_CACHE_HOME", ".cache");
 }
@@ -1,7 +1,11 @@
+use serde::Deserialize;
 use toml;
 use toml::value::*;
+use toml::Spanned;
+use crate::config::{resolve_included_vars, resolve_vars};
 
 use std::collections::HashMap;
+use std::fmt;
 use std::fs::File;
 use std::io::prelude::*;
 use std::path::{Path, PathBuf};
@@ -12,13 +16,26 @@ pub struct Config {
     pub bars: Vec<ConfigGroup>,
     pub floats: Vec<ConfigGroup>,
     pub components: Vec<ConfigGroup>,
+    pub vars: ConfigGroup,
+    pub aliases: ConfigGroup,
     pub config_dir: PathBuf,
+    pub includes: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct ConfigGroup {
     pub name: String,
     pub properties: HashMap<String, Property>,
+    pub location: Location,
+    pub locations: HashMap<String, Location>,
+}
+
+/// position of a table or property in a config file
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Location {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -50,7 +67,22 @@ impl Config {
     }
 }
 
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
 impl ConfigGroup {
+    /// location of a property, falling back to the table it's defined in
+    pub fn get_location(&self, prop: &str) -> &Location {
+        self.locations.get(prop).unwrap_or(&self.location)
+    }
+    /// resolve a path relative to the file the property is set in
+    pub fn get_path(&self, prop: &str, filename: &str) -> String {
+        let file = Path::new(&self.get_location(prop).file);
+        get_path(filename.to_string(), file.parent().unwrap_or_else(|| Path::new("")))
+    }
     pub fn get_int(&self, prop: &str) -> Option<i64> {
         let value_option = self.properties.get(prop);
         if let Some(&Property::Integer(ref val)) = value_option {
@@ -108,11 +140,138 @@ impl ConfigGroup {
 
 pub fn parse_file(filename: &str) -> Result<Config, String> {
     let file_path = Path::new(filename);
-    let config_dir = file_path.parent().ok_or("getting config directory")?;
     let config_file = file_path.file_name().ok_or("getting config filename")?;
 
+    let mut config = read_config(file_path)?;
+
+    // add filename
+    config.global.properties.insert("filename".to_string(), Property::String(
+        config_file.to_string_lossy().to_string()
+    ));
+
+    // vars are resolved first so they can be used in includes
+    resolve_vars(&mut config)?;
+
+    // merge included files, and the files they include
+    let main_path = file_path.canonicalize().unwrap_or_else(|_| file_path.to_path_buf());
+    let mut includes = get_includes(&config)?;
+    let mut index = 0;
+    while index < includes.len() {
+        let path = includes[index].clone();
+        index += 1;
+        if path == main_path {
+            continue;
+        }
+        let mut included = read_config(&path)?;
+        resolve_included_vars(&mut included, &config.vars)?;
+        if included.global.properties.keys().any(|key| key != "include") {
+            warn!(
+                "{}: global properties other than include are ignored in included files",
+                path.display(),
+            );
+        }
+        if !included.vars.properties.is_empty() {
+            warn!("{}: vars are ignored in included files", path.display());
+        }
+        if !included.aliases.properties.is_empty() {
+            warn!("{}: aliases are ignored in included files", path.display());
+        }
+        for nested in get_includes(&included)? {
+            if !includes.contains(&nested) {
+                includes.push(nested);
+            }
+        }
+        merge_groups(&mut config.bars, included.bars, "bar")?;
+        merge_groups(&mut config.floats, included.floats, "float")?;
+        merge_groups(&mut config.components, included.components, "component")?;
+        config.includes.push(path.to_string_lossy().to_string());
+    }
+
+    resolve_extends(&mut config.components)?;
+
+    // #[cfg(debug_assertions)]
+    // println!("{:#?}", config);
+
+    Ok(config)
+}
+
+/// included files, relative to the directory of the file including them
+fn get_includes(config: &Config) -> Result<Vec<PathBuf>, String> {
+    let location = config.global.get_location("include");
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for pattern in config.global.get_string_vec("include") {
+        let full_pattern = config.config_dir.join(&pattern);
+        let matches = glob::glob(&full_pattern.to_string_lossy())
+            .map_err(|err| format!("{}: {} in {:?}", location, err, pattern))?
+            .filter_map(Result::ok)
+            .map(|path| path.canonicalize().unwrap_or(path))
+            .collect::<Vec<PathBuf>>();
+        let is_glob = pattern.contains(&['*', '?', '['][..]);
+        if matches.is_empty() && !is_glob {
+            return Err(format!("{}: cannot find included file {:?}", location, pattern));
+        }
+        for path in matches {
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+    }
+    Ok(paths)
+}
+
+fn merge_groups(
+    groups: &mut Vec<ConfigGroup>,
+    included: Vec<ConfigGroup>,
+    kind: &str,
+) -> Result<(), String> {
+    for group in included {
+        if let Some(existing) = groups.iter().find(|g| g.name == group.name) {
+            return Err(format!(
+                "{}: {} #{} is already defined at {}",
+                group.location, kind, group.name, existing.location,
+            ));
+        }
+        groups.push(group);
+    }
+    Ok(())
+}
+
+/// copy inherited properties onto each component, nearest first
+fn resolve_extends(components: &mut Vec<ConfigGroup>) -> Result<(), String> {
+    let originals = components.clone();
+    for component in components.iter_mut() {
+        let mut chain = vec![component.name.clone()];
+        let mut parent_name = component.get_string("extends");
+        while let Some(name) = parent_name {
+            let location = component.get_location("extends");
+            if chain.contains(&name) {
+                chain.push(name);
+                return Err(format!(
+                    "{}: component #{} has circular inheritance (#{})",
+                    location, component.name, chain.join(" -> #"),
+                ));
+            }
+            let parent = originals.iter().find(|c| c.name == name).ok_or_else(|| {
+                format!("{}: component #{} extends missing component #{}", location, component.name, name)
+            })?;
+            for (prop, value) in parent.properties.iter() {
+                if !component.properties.contains_key(prop) {
+                    component.properties.insert(prop.clone(), value.clone());
+                    component.locations.insert(prop.clone(), parent.get_location(prop).clone());
+                }
+            }
+            chain.push(name);
+            parent_name = parent.get_string("extends");
+        }
+    }
+    Ok(())
+}
+
+fn read_config(file_path: &Path) -> Result<Config, String> {
+    let config_dir = file_path.parent().ok_or("getting config directory")?;
+
     // get file
-    let mut file_result = File::open(filename).map_err(|x| {
+    let mut file_result = File::open(file_path).map_err(|x| {
         format!("{}: {}", file_path.display(), x.to_string().to_lowercase())
     })?;
 
@@ -122,21 +281,29 @@ pub fn parse_file(filename: &str) -> Result<Config, String> {
         .map_err(|x| x.to_string())?;
 
     // parse file
-    let parsed = contents.parse::<toml::Value>().map_err(|x| x.to_string())?;
+    let parsed = contents.parse::<toml::Value>().map_err(|x| {
+        format!("{}: {}", file_path.display(), x)
+    })?;
+
+    let locator = Locator::new(&file_path.display().to_string(), &contents);
 
     // getters
 
-    let get_table_config = |&(key, value): &(&String, &Value)| {
+    let get_table_config = |kind: &str, (key, value): (&String, &Value)| {
         // get properties
         let mut properties: HashMap<String, Property> = HashMap::new();
-        value.as_table().unwrap().iter().for_each(|(key, value)| {
-            let key_str = key.to_string();
-            properties.insert(key_str, value_to_property(value));
+        let mut locations: HashMap<String, Location> = HashMap::new();
+        value.as_table().unwrap().iter().for_each(|(prop, value)| {
+            let prop_str = prop.to_string();
+            properties.insert(prop_str.clone(), value_to_property(value));
+            locations.insert(prop_str, locator.get_property(kind, key, prop));
         });
 
         ConfigGroup {
             name: key.to_string(),
             properties,
+            location: locator.get_table(kind, key),
+            locations,
         }
     };
 
@@ -145,14 +312,11 @@ pub fn parse_file(filename: &str) -> Result<Config, String> {
 
         if let Some(Some(component_table)) = component_option.map(|d| d.as_table()) {
             // get all component tables
-            let components: Vec<(&String, &Value)> = component_table
+            component_table
                 .iter()
                 .filter(|&(_k, v)| v.is_table())
-                .collect();
-            let component_configs: Vec<ConfigGroup> =
-                components.iter().map(get_table_config).collect();
-
-            component_configs
+                .map(|entry| get_table_config(name, entry))
+                .collect()
         } else {
             Vec::new()
         }
@@ -162,38 +326,70 @@ pub fn parse_file(filename: &str) -> Result<Config, String> {
 
     let global = {
         let mut properties: HashMap<String, Property> = HashMap::new();
+        let mut locations: HashMap<String, Location> = HashMap::new();
         parsed.as_table().unwrap().iter().for_each(|(key, value)| {
             if !value.is_table() {
                 let key_str = key.to_string();
-                properties.insert(key_str, value_to_property(value));
+                properties.insert(key_str.clone(), value_to_property(value));
+                locations.insert(key_str, locator.get_global(key));
             }
         });
 
-        // add filename
-        properties.insert("filename".to_string(), Property::String(
-            config_file.to_string_lossy().to_string()
-        ));
-
         ConfigGroup {
             name: "global".to_string(),
             properties,
+            location: locator.get_location(0),
+            locations,
+        }
+    };
+
+    // get single tables like [vars]
+
+    let get_single_table = |name: &str| {
+        let mut properties: HashMap<String, Property> = HashMap::new();
+        let mut locations: HashMap<String, Location> = HashMap::new();
+        if let Some(table) = parsed.get(name).and_then(|v| v.as_table()) {
+            table.iter().for_each(|(key, value)| {
+                let key_str = key.to_string();
+                properties.insert(key_str.clone(), value_to_property(value));
+                locations.insert(key_str, locator.get_entry(name, key));
+            });
+        }
+
+        ConfigGroup {
+            name: name.to_string(),
+            properties,
+            location: locator.get_header(name),
+            locations,
         }
     };
 
     // root
 
-    let config = Config {
+    Ok(Config {
         global,
         bars: get_table_config_list("bar"),
         floats: get_table_config_list("float"),
         components: get_table_config_list("component"),
+        vars: get_single_table("vars"),
+        aliases: get_single_table("alias"),
         config_dir: config_dir.to_path_buf(),
-    };
-
-    // #[cfg(debug_assertions)]
-    // println!("{:#?}", config);
+        includes: Vec::new(),
+    })
+}
 
-    Ok(config)
+/// write files into a fresh temporary directory
+#[cfg(test)]
+pub fn write_test_files(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir()
+        .join(format!("{}-test-{}-{}", crate::NAME, name, std::process::id()));
+    std::fs::remove_dir_all(&dir).ok();
+    for (path, contents) in files {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+    dir
 }
 
 fn get_path(file: String, directory: &Path) -> String {
@@ -231,3 +427,267 @@ fn value_to_property(value: &Value) -> Property {
         _ => Property::Null,
     }
 }
+
+// locations
+
+type SpanTable = HashMap<String, HashMap<String, Spanned<Value>>>;
+
+#[derive(Deserialize, Default)]
+struct Spans {
+    #[serde(default)]
+    bar: SpanTable,
+    #[serde(default)]
+    float: SpanTable,
+    #[serde(default)]
+    component: SpanTable,
+    #[serde(default)]
+    vars: HashMap<String, Spanned<Value>>,
+    #[serde(default)]
+    alias: HashMap<String, Spanned<Value>>,
+}
+
+/// maps tables and properties back to their position in the source
+struct Locator<'a> {
+    file: String,
+    contents: &'a str,
+    spans: Spans,
+}
+
+impl<'a> Locator<'a> {
+    fn new(file: &str, contents: &'a str) -> Self {
+        // fails for nested tables, so properties fall back to their table
+        let spans = toml::from_str(contents).unwrap_or_else(|err| {
+            warn!("{}: errors will point at tables instead of properties ({})", file, err);
+            Spans::default()
+        });
+        Locator {
+            file: file.to_string(),
+            contents,
+            spans,
+        }
+    }
+
+    /// convert a byte offset to a location
+    fn get_location(&self, offset: usize) -> Location {
+        let before = &self.contents[..offset];
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        Location {
+            file: self.file.clone(),
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+
+    /// location of the first non-whitespace character on the line
+    fn get_line_start(&self, offset: usize) -> Location {
+        let line_start = self.contents[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let indent = self.contents[line_start..offset].len()
+            - self.contents[line_start..offset].trim_start().len();
+        self.get_location(line_start + indent)
+    }
+
+    fn get_table(&self, kind: &str, name: &str) -> Location {
+        self.get_header(&format!("{}.{}", kind, name))
+    }
+
+    fn get_header(&self, header: &str) -> Location {
+        let mut offset = 0;
+        for line in self.contents.split('\n') {
+            let trimmed = line.trim();
+            if trimmed.starts_with('[') && !trimmed.starts_with("[[") {
+                let table = trimmed
+                    .split(']')
+                    .next()
+                    .unwrap_or("")
+                    .chars()
+                    .filter(|c| !"[\"' \t".contains(*c))
+                    .collect::<String>();
+                if table == header {
+                    return self.get_location(offset + line.len() - line.trim_start().len());
+                }
+            }
+            offset += line.len() + 1;
+        }
+        self.get_location(0)
+    }
+
+    fn get_property(&self, kind: &str, name: &str, prop: &str) -> Location {
+        let table = match kind {
+            "bar" => &self.spans.bar,
+            "float" => &self.spans.float,
+            _ => &self.spans.component,
+        };
+        table.get(name)
+            .and_then(|props| props.get(prop))
+            .map(|span| self.get_line_start(span.start()))
+            .unwrap_or_else(|| self.get_table(kind, name))
+    }
+
+    fn get_entry(&self, header: &str, key: &str) -> Location {
+        let table = match header {
+            "vars" => &self.spans.vars,
+            _ => &self.spans.alias,
+        };
+        table.get(key)
+            .map(|span| self.get_line_start(span.start()))
+            .unwrap_or_else(|| self.get_header(header))
+    }
+
+    fn get_global(&self, key: &str) -> Location {
+        let mut offset = 0;
+        for line in self.contents.split('\n') {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with('[') {
+                break;
+            }
+            if trimmed.starts_with(key) {
+                let rest = trimmed[key.len()..].trim_start();
+                if rest.starts_with('=') {
+                    return self.get_location(offset + line.len() - trimmed.len());
+                }
+            }
+            offset += line.len() + 1;
+        }
+        self.get_location(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(dir: &Path, filename: &str) -> Result<Config, String> {
+        parse_file(&dir.join(filename).to_string_lossy())
+    }
+
+    fn get_names(groups: &[ConfigGroup]) -> Vec<&str> {
+        let mut names = groups.iter().map(|group| group.name.as_str()).collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn glob_includes() {
+        let dir = write_test_files("include-glob", &[
+            ("config.toml", "include = [\"components/*.toml\", \"extra/*.toml\"]\n[bar.main]\n"),
+            ("components/clock.toml", "[component.clock]\ntype = \"clock\"\n"),
+            ("components/cpu.toml", "[component.cpu]\ntype = \"cpu\"\n[float.info]\n"),
+            ("components/notes.txt", "not toml"),
+        ]);
+        let config = parse(&dir, "config.toml").unwrap();
+        assert_eq!(get_names(&config.components), vec!["clock", "cpu"]);
+        assert_eq!(get_names(&config.bars), vec!["main"]);
+        assert_eq!(get_names(&config.floats), vec!["info"]);
+        assert_eq!(config.includes.len(), 2);
+    }
+
+    #[test]
+    fn missing_includes() {
+        let dir = write_test_files("include-missing", &[
+            ("config.toml", "include = [\"missing.toml\"]\n"),
+        ]);
+        let err = parse(&dir, "config.toml").unwrap_err();
+        assert!(err.ends_with("cannot find included file \"missing.toml\""), "{}", err);
+    }
+
+    #[test]
+    fn duplicate_names() {
+        let dir = write_test_files("include-duplicate", &[
+            ("config.toml", "include = [\"clock.toml\"]\n[component.clock]\ntype = \"clock\"\n"),
+            ("clock.toml", "\n[component.clock]\ntype = \"clock\"\n"),
+        ]);
+        let err = parse(&dir, "config.toml").unwrap_err();
+        assert!(err.contains("clock.toml:2:1: component #clock is already defined at "), "{}", err);
+        assert!(err.ends_with("config.toml:2:1"), "{}", err);
+    }
+
+    #[test]
+    fn relative_includes() {
+        let dir = write_test_files("include-relative", &[
+            ("nested/config.toml", "include = [\"../shared/bar.toml\"]\n"),
+            ("shared/bar.toml", "[bar.shared]\nlayout = []\n"),
+        ]);
+        let config = parse(&dir.join("nested"), "config.toml").unwrap();
+        assert_eq!(get_names(&config.bars), vec!["shared"]);
+        let path = dir.join("shared/bar.toml").canonicalize().unwrap();
+        assert_eq!(config.includes, vec![path.to_string_lossy().to_string()]);
+    }
+
+    #[test]
+    fn nested_includes() {
+        let dir = write_test_files("include-nested", &[
+            ("config.toml", "include = [\"${parts}/bar.toml\"]\n[vars]\nparts = \"parts\"\n"),
+            ("parts/bar.toml", "include = [\"widgets/*.toml\"]\n[bar.main]\nlayout = []\n"),
+            ("parts/widgets/logo.toml", "[component.logo]\ntype = \"image\"\nsrc = \"logo.png\"\n"),
+            ("parts/widgets/logo.png", ""),
+        ]);
+        let config = parse(&dir, "config.toml").unwrap();
+        assert_eq!(get_names(&config.bars), vec!["main"]);
+        assert_eq!(get_names(&config.components), vec!["logo"]);
+        assert_eq!(config.includes.len(), 2);
+        let logo = dir.join("parts/widgets/logo.png").canonicalize().unwrap();
+        assert_eq!(
+            config.components[0].get_path("src", "logo.png"),
+            logo.to_string_lossy().to_string(),
+        );
+    }
+
+    #[test]
+    fn multi_level_extends() {
+        let dir = write_test_files("extends-levels", &[("config.toml", r#"
+[component.base]
+halign = "end"
+class = "base"
+[component.mid]
+extends = "base"
+type = "clock"
+class = "mid"
+[component.leaf]
+extends = "mid"
+interval = 5
+"#)]);
+        let config = parse(&dir, "config.toml").unwrap();
+        let leaf = config.components.iter().find(|c| c.name == "leaf").unwrap();
+        assert_eq!(leaf.get_str_or("type", ""), "clock");
+        assert_eq!(leaf.get_str_or("class", ""), "mid");
+        assert_eq!(leaf.get_str_or("halign", ""), "end");
+        assert_eq!(leaf.get_int("interval"), Some(5));
+        // inherited properties point to where they're defined
+        assert_eq!(leaf.get_location("halign").line, 3);
+        assert_eq!(leaf.get_location("class").line, 8);
+        // parents are left alone
+        let base = config.components.iter().find(|c| c.name == "base").unwrap();
+        assert!(base.get_string("type").is_none());
+    }
+
+    #[test]
+    fn circular_extends() {
+        let dir = write_test_files("extends-cycle", &[("config.toml", r#"
+[component.a]
+extends = "b"
+[component.b]
+extends = "c"
+[component.c]
+extends = "a"
+"#)]);
+        let err = parse(&dir, "config.toml").unwrap_err();
+        assert!(err.ends_with("component #a has circular inheritance (#a -> #b -> #c -> #a)"), "{}", err);
+
+        let dir = write_test_files("extends-self", &[("config.toml", r#"
+[component.a]
+extends = "a"
+"#)]);
+        let err = parse(&dir, "config.toml").unwrap_err();
+        assert!(err.ends_with("component #a has circular inheritance (#a -> #a)"), "{}", err);
+    }
+
+    #[test]
+    fn missing_extends() {
+        let dir = write_test_files("extends-missing", &[("config.toml", r#"
+[component.a]
+extends = "nope"
+"#)]);
+        let err = parse(&dir, "config.toml").unwrap_err();
+        assert!(err.ends_with("component #a extends missing component #nope"), "{}", err);
+    }
+}
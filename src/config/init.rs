@@ -0,0 +1,123 @@
+use crate::wm::{self, WMType};
+use std::fs;
+use std::path::Path;
+
+/// write a starter config and theme for the current window manager and monitors
+pub fn init(config_path: &str) -> bool {
+    let config_file = Path::new(config_path);
+    let theme_file = config_file.with_file_name("theme.css");
+
+    for path in [config_file, theme_file.as_path()].iter() {
+        if path.exists() {
+            error!("{} already exists", path.display());
+            return false;
+        }
+    }
+
+    // monitor info needs GTK to be initialized
+    let monitors = if gtk::init().is_ok() {
+        wm::gtk::get_monitor_coords()
+            .into_iter()
+            .map(|(_, _, name)| name)
+            .collect()
+    } else {
+        warn!("failed to initialize GTK, assuming one monitor");
+        Vec::new()
+    };
+    let wm_type = wm::get_wm_type(&wm::xcb::get_wm_name());
+
+    let config = get_config(wm_type, &monitors);
+    let theme = include_str!("init.css");
+
+    let result = config_file
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(config_file, config))
+        .and_then(|_| fs::write(&theme_file, theme));
+
+    match result {
+        Ok(_) => {
+            info!("created {} and {}", config_file.display(), theme_file.display());
+            true
+        },
+        Err(err) => {
+            error!("{}", err.to_string().to_lowercase());
+            false
+        },
+    }
+}
+
+fn get_config(wm_type: WMType, monitors: &[String]) -> String {
+    let layout = match wm_type {
+        WMType::I3 => vec!["workspaces", "mode", "title", "clock"],
+        WMType::Bsp | WMType::Unknown => vec!["workspaces", "title", "clock"],
+    };
+    let layout = layout
+        .iter()
+        .map(|name| format!("{:?}", name))
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    let mut config = String::from("theme = \"theme.css\"\n\n# bars\n");
+
+    // one bar per monitor
+    if monitors.len() > 1 {
+        for (index, name) in monitors.iter().enumerate() {
+            let monitor = if name == "[unknown]" {
+                index.to_string()
+            } else {
+                format!("{:?}", name)
+            };
+            config.push_str(&format!(
+                "\n[bar.bar{}]\nclass = \"bar\"\nmonitor = {}\nlayout = [{}]\n",
+                index, monitor, layout,
+            ));
+        }
+    } else {
+        config.push_str(&format!(
+            "\n[bar.bar]\nclass = \"bar\"\nmonitor = \"primary\"\nlayout = [{}]\n",
+            layout,
+        ));
+    }
+
+    config.push_str("\n# components\n\n[component.workspaces]\ntype = \"workspaces\"\n");
+    match wm_type {
+        WMType::I3 => config.push_str("\n[component.mode]\ntype = \"i3-mode\"\n"),
+        WMType::Bsp => {},
+        // EWMH desktops usually share a viewport, so they can't be split by monitor
+        WMType::Unknown => config.push_str("show-all = true\n"),
+    }
+    config.push_str(concat!(
+        "\n[component.title]\ntype = \"window-title\"\nhalign = \"center\"\n",
+        "\n[component.clock]\ntype = \"clock\"\ntimestamp = \"%a %b %e %H:%M\"\n",
+    ));
+
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use toml::Value;
+
+    fn get_component<'a>(config: &'a Value, name: &str) -> &'a Value {
+        &config["component"][name]
+    }
+
+    #[test]
+    fn configs_for_each_wm() {
+        let monitors = vec!["DP-1".to_string(), "HDMI-1".to_string()];
+        for wm_type in [WMType::I3, WMType::Bsp, WMType::Unknown].iter() {
+            let config = get_config(*wm_type, &monitors).parse::<Value>().unwrap();
+            assert_eq!(config["bar"]["bar1"]["monitor"].as_str(), Some("HDMI-1"));
+            let workspaces = get_component(&config, "workspaces");
+            let show_all = workspaces.get("show-all").and_then(Value::as_bool);
+            let has_mode = config["component"].get("mode").is_some();
+            match wm_type {
+                WMType::I3 => assert_eq!((show_all, has_mode), (None, true)),
+                WMType::Bsp => assert_eq!((show_all, has_mode), (None, false)),
+                WMType::Unknown => assert_eq!((show_all, has_mode), (Some(true), false)),
+            }
+        }
+    }
+}
@@ -1,7 +1,19 @@
 mod args;
+mod check;
 mod env;
 mod file;
+mod init;
+mod options;
+mod print;
+mod schema;
+mod vars;
 
 pub use args::*;
+pub use check::*;
 pub use env::*;
 pub use file::*;
+pub use init::*;
+pub use options::*;
+pub use print::*;
+pub use schema::*;
+pub use vars::*;
@@ -0,0 +1,302 @@
+use serde::de::{
+    self, Deserialize, DeserializeOwned, DeserializeSeed, Deserializer, IntoDeserializer,
+    MapAccess, SeqAccess, Visitor,
+};
+use serde::ser::{Serialize, Serializer};
+use serde::forward_to_deserialize_any;
+use toml::value::{Table, Value};
+
+use std::collections::HashMap;
+use std::fmt;
+use std::vec;
+
+use crate::config::{ConfigGroup, Property, Type};
+
+/// maps an option's rust type to the property type it's validated against
+pub trait PropertyType {
+    const TYPE: Type;
+}
+
+impl PropertyType for String {
+    const TYPE: Type = Type::String;
+}
+impl PropertyType for i64 {
+    const TYPE: Type = Type::Integer;
+}
+impl PropertyType for f64 {
+    const TYPE: Type = Type::Float;
+}
+impl PropertyType for bool {
+    const TYPE: Type = Type::Boolean;
+}
+impl PropertyType for Vec<String> {
+    const TYPE: Type = Type::Strings;
+}
+impl PropertyType for Vec<Property> {
+    const TYPE: Type = Type::Array;
+}
+impl<T: PropertyType> PropertyType for Option<T> {
+    const TYPE: Type = T::TYPE;
+}
+
+/// a monitor index or a name, "primary" or "all"
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(untagged)]
+pub enum Monitor {
+    Index(i64),
+    Name(String),
+}
+
+impl From<i64> for Monitor {
+    fn from(index: i64) -> Self {
+        Monitor::Index(index)
+    }
+}
+
+impl PropertyType for Monitor {
+    const TYPE: Type = Type::Monitor;
+}
+
+/// read options from a config table, dropping properties with the wrong type
+pub fn get_options<T: DeserializeOwned + Default>(config: &ConfigGroup) -> T {
+    let mut properties = config.properties.clone();
+    loop {
+        match T::deserialize(Property::Object(properties.clone())) {
+            Ok(options) => return options,
+            Err(err) => {
+                let key = err.path.first().cloned().unwrap_or_default();
+                warn!("{}: {} in #{}", config.get_location(&key), err, config.name);
+                if properties.remove(&key).is_none() {
+                    return T::default();
+                }
+            },
+        }
+    }
+}
+
+/// convert options back to a table
+pub fn get_table<T: Serialize>(options: &T) -> Table {
+    match Value::try_from(options) {
+        Ok(Value::Table(table)) => table,
+        _ => Table::new(),
+    }
+}
+
+// errors
+
+#[derive(Debug)]
+pub struct OptionsError {
+    path: Vec<String>,
+    message: String,
+}
+
+impl OptionsError {
+    fn with_key(mut self, key: &str) -> Self {
+        self.path.insert(0, key.to_string());
+        self
+    }
+}
+
+impl fmt::Display for OptionsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path.join("."), self.message)
+        }
+    }
+}
+
+impl std::error::Error for OptionsError {}
+
+impl de::Error for OptionsError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        OptionsError {
+            path: Vec::new(),
+            message: msg.to_string(),
+        }
+    }
+}
+
+// property as a deserializer
+
+impl<'de> Deserializer<'de> for Property {
+    type Error = OptionsError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, OptionsError> {
+        match self {
+            Property::String(s) => visitor.visit_string(s),
+            Property::Integer(i) => visitor.visit_i64(i),
+            Property::Float(f) => visitor.visit_f64(f),
+            Property::Boolean(b) => visitor.visit_bool(b),
+            Property::Array(arr) => visitor.visit_seq(PropertySeq(arr.into_iter())),
+            Property::Object(obj) => visitor.visit_map(PropertyMap {
+                iter: obj.into_iter(),
+                value: None,
+            }),
+            Property::Null => visitor.visit_unit(),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, OptionsError> {
+        match self {
+            Property::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, OptionsError> {
+        match self {
+            Property::String(s) => s.into_deserializer().deserialize_enum(name, variants, visitor),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, OptionsError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+struct PropertySeq(vec::IntoIter<Property>);
+
+impl<'de> SeqAccess<'de> for PropertySeq {
+    type Error = OptionsError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, OptionsError> {
+        match self.0.next() {
+            Some(value) => seed.deserialize(value).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+struct PropertyMap {
+    iter: std::collections::hash_map::IntoIter<String, Property>,
+    value: Option<(String, Property)>,
+}
+
+impl<'de> MapAccess<'de> for PropertyMap {
+    type Error = OptionsError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, OptionsError> {
+        match self.iter.next() {
+            Some((key, value)) => {
+                let result = seed.deserialize(key.clone().into_deserializer());
+                self.value = Some((key, value));
+                result.map(Some)
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, OptionsError> {
+        match self.value.take() {
+            Some((key, value)) => seed.deserialize(value).map_err(|err| err.with_key(&key)),
+            None => Err(de::Error::custom("value is missing")),
+        }
+    }
+}
+
+// property to any serializer
+
+impl Serialize for Property {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Property::String(s) => serializer.serialize_str(s),
+            Property::Integer(i) => serializer.serialize_i64(*i),
+            Property::Float(f) => serializer.serialize_f64(*f),
+            Property::Boolean(b) => serializer.serialize_bool(*b),
+            Property::Array(arr) => arr.serialize(serializer),
+            Property::Object(obj) => obj.serialize(serializer),
+            Property::Null => serializer.serialize_none(),
+        }
+    }
+}
+
+// property from any deserializer
+
+impl<'de> Deserialize<'de> for Property {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Property, D::Error> {
+        deserializer.deserialize_any(PropertyVisitor)
+    }
+}
+
+struct PropertyVisitor;
+
+impl<'de> Visitor<'de> for PropertyVisitor {
+    type Value = Property;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a config value")
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<Property, E> {
+        Ok(Property::Boolean(value))
+    }
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Property, E> {
+        Ok(Property::Integer(value))
+    }
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Property, E> {
+        if value <= i64::max_value() as u64 {
+            Ok(Property::Integer(value as i64))
+        } else {
+            Err(E::custom("integer is too large"))
+        }
+    }
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Property, E> {
+        Ok(Property::Float(value))
+    }
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Property, E> {
+        Ok(Property::String(value.to_string()))
+    }
+    fn visit_string<E: de::Error>(self, value: String) -> Result<Property, E> {
+        Ok(Property::String(value))
+    }
+    fn visit_unit<E: de::Error>(self) -> Result<Property, E> {
+        Ok(Property::Null)
+    }
+    fn visit_none<E: de::Error>(self) -> Result<Property, E> {
+        Ok(Property::Null)
+    }
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Property, D::Error> {
+        Deserialize::deserialize(deserializer)
+    }
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Property, A::Error> {
+        let mut arr = Vec::new();
+        while let Some(item) = seq.next_element()? {
+            arr.push(item);
+        }
+        Ok(Property::Array(arr))
+    }
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Property, A::Error> {
+        let mut obj = HashMap::new();
+        while let Some((key, value)) = map.next_entry()? {
+            obj.insert(key, value);
+        }
+        Ok(Property::Object(obj))
+    }
+}
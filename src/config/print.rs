@@ -0,0 +1,72 @@
+use crate::config::{get_table, parse_file, Config, ConfigGroup};
+use toml::value::{Table, Value};
+
+/// print the config with includes, defaults and paths resolved
+pub fn print_config(config_path: &str, format: &str) -> bool {
+    let config = match parse_file(config_path) {
+        Ok(config) => config,
+        Err(err) => {
+            error!("{}", err);
+            return false;
+        },
+    };
+
+    let resolved = Value::Table(get_resolved(&config));
+    let output = match format {
+        "toml" => toml::to_string_pretty(&resolved).map_err(|err| err.to_string()),
+        "json" => serde_json::to_string_pretty(&resolved).map_err(|err| err.to_string()),
+        _ => Err(format!("unknown format {:?}, expected toml or json", format)),
+    };
+
+    match output {
+        Ok(output) => {
+            println!("{}", output.trim_end());
+            true
+        },
+        Err(err) => {
+            error!("{}", err);
+            false
+        },
+    }
+}
+
+/// the config as a table with includes, defaults and paths resolved
+pub fn get_resolved(config: &Config) -> Table {
+    let mut root = get_table(&config.global.properties);
+
+    // global
+    root.insert("theme".to_string(), Value::String(config.get_theme()));
+    root.insert("filename".to_string(), Value::String(config.get_filename()));
+    root.entry("enable-ipc".to_string()).or_insert(Value::Boolean(true));
+    root.entry("enable-dbus".to_string()).or_insert(Value::Boolean(false));
+    if !config.includes.is_empty() {
+        root.insert("include".to_string(), Value::Array(
+            config.includes.iter().cloned().map(Value::String).collect()
+        ));
+    }
+
+    // tables
+    if !config.aliases.properties.is_empty() {
+        root.insert("alias".to_string(), Value::Table(get_table(&config.aliases.properties)));
+    }
+    insert_tables(&mut root, "bar", &config.bars, crate::bar::get_table);
+    insert_tables(&mut root, "float", &config.floats, crate::float::get_table);
+    insert_tables(&mut root, "component", &config.components, crate::components::get_resolved);
+
+    root
+}
+
+fn insert_tables(
+    root: &mut Table,
+    kind: &str,
+    groups: &[ConfigGroup],
+    get_table: fn(&ConfigGroup) -> Table,
+) {
+    if !groups.is_empty() {
+        let tables = groups
+            .iter()
+            .map(|group| (group.name.clone(), Value::Table(get_table(group))))
+            .collect::<Table>();
+        root.insert(kind.to_string(), Value::Table(tables));
+    }
+}
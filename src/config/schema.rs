@@ -0,0 +1,310 @@
+use std::fmt;
+
+use crate::config::{Config, ConfigGroup, Location, Property};
+
+/// the type a property is expected to have
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Type {
+    String,
+    Integer,
+    Float,
+    Boolean,
+    Strings,
+    Array,
+    Monitor,
+}
+
+/// property names and types accepted by a config table
+pub type Schema = &'static [(&'static str, Type)];
+
+pub const GLOBAL: Schema = &[
+    ("theme", Type::String),
+    ("enable-ipc", Type::Boolean),
+    ("enable-dbus", Type::Boolean),
+    ("include", Type::Strings),
+    ("filename", Type::String),
+];
+
+#[derive(Debug)]
+pub struct ConfigError {
+    pub location: Location,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Type::String => "a string",
+            Type::Integer => "an integer",
+            Type::Float => "a number",
+            Type::Boolean => "a boolean",
+            Type::Strings => "an array of strings",
+            Type::Array => "an array",
+            Type::Monitor => "a monitor index or name",
+        })
+    }
+}
+
+impl Type {
+    pub fn matches(&self, property: &Property) -> bool {
+        match (self, property) {
+            (Type::String, Property::String(_)) => true,
+            (Type::Integer, Property::Integer(_)) => true,
+            (Type::Float, Property::Float(_)) => true,
+            (Type::Float, Property::Integer(_)) => true,
+            (Type::Monitor, Property::Integer(_)) => true,
+            (Type::Monitor, Property::String(_)) => true,
+            (Type::Boolean, Property::Boolean(_)) => true,
+            (Type::Strings, Property::Array(items)) => items.iter().all(|item| {
+                if let Property::String(_) = item { true } else { false }
+            }),
+            (Type::Array, Property::Array(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+fn get_type_name(property: &Property) -> &'static str {
+    match property {
+        Property::String(_) => "a string",
+        Property::Integer(_) => "an integer",
+        Property::Float(_) => "a float",
+        Property::Array(_) => "an array",
+        Property::Boolean(_) => "a boolean",
+        Property::Object(_) => "a table",
+        Property::Null => "null",
+    }
+}
+
+impl Config {
+    /// check every table against the properties it accepts
+    pub fn validate(&self) -> Vec<ConfigError> {
+        let mut errors = Vec::new();
+
+        check_properties(&self.global, "global", &[GLOBAL], &mut errors);
+
+        for bar in self.bars.iter() {
+            let name = format!("bar #{}", bar.name);
+            check_properties(bar, &name, &[crate::bar::PROPERTIES], &mut errors);
+            check_layout(self, bar, &name, &mut errors);
+        }
+
+        for float in self.floats.iter() {
+            let name = format!("float #{}", float.name);
+            check_properties(float, &name, &[crate::float::PROPERTIES], &mut errors);
+            check_layout(self, float, &name, &mut errors);
+        }
+
+        for component in self.components.iter() {
+            let name = format!("component #{}", component.name);
+            match component.properties.get("type") {
+                Some(Property::String(type_)) => {
+                    match crate::components::get_properties(type_) {
+                        Some(properties) => {
+                            let schemas = [crate::components::PROPERTIES, properties];
+                            check_properties(component, &name, &schemas, &mut errors);
+                        },
+                        None => errors.push(ConfigError {
+                            location: component.get_location("type").clone(),
+                            message: format!("{} has an unknown type {:?}", name, type_),
+                        }),
+                    }
+                },
+                Some(property) => errors.push(ConfigError {
+                    location: component.get_location("type").clone(),
+                    message: format!(
+                        "type of {} should be a string, found {}",
+                        name,
+                        get_type_name(property),
+                    ),
+                }),
+                // components without a type can still be used as a base
+                // for others, which check the properties they inherit
+                None => if !self.is_extended(&component.name) {
+                    errors.push(ConfigError {
+                        location: component.location.clone(),
+                        message: format!("{} is missing a type", name),
+                    });
+                },
+            }
+            check_layout(self, component, &name, &mut errors);
+        }
+
+        for (alias, property) in self.aliases.properties.iter() {
+            if let Property::String(_) = property {
+                continue;
+            }
+            errors.push(ConfigError {
+                location: self.aliases.get_location(alias).clone(),
+                message: format!(
+                    "alias {} should be a string, found {}",
+                    alias,
+                    get_type_name(property),
+                ),
+            });
+        }
+
+        errors.sort_by(|a, b| a.location.cmp(&b.location));
+
+        errors
+    }
+
+    fn is_extended(&self, name: &str) -> bool {
+        self.components
+            .iter()
+            .any(|c| c.get_str_or("extends", "") == name)
+    }
+}
+
+fn check_properties(
+    group: &ConfigGroup,
+    name: &str,
+    schemas: &[Schema],
+    errors: &mut Vec<ConfigError>,
+) {
+    for (prop, value) in group.properties.iter() {
+        let type_opt = schemas
+            .iter()
+            .flat_map(|schema| schema.iter())
+            .find(|(key, _)| key == prop)
+            .map(|(_, type_)| type_);
+
+        match type_opt {
+            Some(type_) => {
+                if !type_.matches(value) {
+                    errors.push(ConfigError {
+                        location: group.get_location(prop).clone(),
+                        message: format!(
+                            "{} of {} should be {}, found {}",
+                            prop,
+                            name,
+                            type_,
+                            get_type_name(value),
+                        ),
+                    });
+                }
+            },
+            None => errors.push(ConfigError {
+                location: group.get_location(prop).clone(),
+                message: format!("unknown property {} in {}", prop, name),
+            }),
+        }
+    }
+}
+
+fn check_layout(
+    config: &Config,
+    group: &ConfigGroup,
+    name: &str,
+    errors: &mut Vec<ConfigError>,
+) {
+    for component in group.get_string_vec("layout") {
+        if !config.components.iter().any(|c| c.name == component) {
+            errors.push(ConfigError {
+                location: group.get_location("layout").clone(),
+                message: format!("missing component #{} in layout of {}", component, name),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{parse_file, write_test_files};
+
+    fn parse(name: &str, contents: &str) -> crate::config::Config {
+        let dir = write_test_files(name, &[("config.toml", contents)]);
+        parse_file(&dir.join("config.toml").to_string_lossy()).unwrap()
+    }
+
+    fn validate(name: &str, contents: &str) -> Vec<String> {
+        parse(name, contents).validate().into_iter().map(|err| err.message).collect()
+    }
+
+    #[test]
+    fn unknown_keys() {
+        let errors = validate("schema-unknown", r#"
+            colour = "red"
+            [bar.main]
+            layout = ["clock"]
+            height = 20
+            [component.clock]
+            type = "clock"
+            colour = "red"
+        "#);
+        assert_eq!(errors, vec![
+            "unknown property colour in global",
+            "unknown property height in bar #main",
+            "unknown property colour in component #clock",
+        ]);
+    }
+
+    #[test]
+    fn wrong_types() {
+        let errors = validate("schema-types", r#"
+            enable-ipc = "yes"
+            [bar.main]
+            position = 1
+            monitor = "HDMI-1"
+            layout = ["clock"]
+            [component.clock]
+            type = "clock"
+            interval = "1"
+            class = 2
+            timestamp = 1979-05-27
+        "#);
+        assert_eq!(errors, vec![
+            "enable-ipc of global should be a boolean, found a string",
+            "position of bar #main should be a string, found an integer",
+            "interval of component #clock should be an integer, found a string",
+            "class of component #clock should be a string, found an integer",
+            "timestamp of component #clock should be a string, found null",
+        ]);
+    }
+
+    #[test]
+    fn component_schemas() {
+        let errors = validate("schema-components", r#"
+            [bar.main]
+            layout = ["clock", "menu", "missing"]
+            [component.clock]
+            type = "clock"
+            timestamp = "%H:%M"
+            items = []
+            [component.menu]
+            type = "dropdown"
+            items = []
+            [component.thing]
+            type = "thing"
+            [component.untyped]
+            halign = "end"
+        "#);
+        assert_eq!(errors, vec![
+            "missing component #missing in layout of bar #main",
+            "unknown property items in component #clock",
+            "component #thing has an unknown type \"thing\"",
+            "component #untyped is missing a type",
+        ]);
+    }
+
+    #[test]
+    fn nested_table_locations() {
+        // spans can't be read with a nested table, so errors point at the table
+        let config = parse("schema-nested", r#"
+            [component.clock]
+            type = "clock"
+            colour = "red"
+            [component.clock.extra]
+        "#);
+        let errors = config.validate();
+        assert_eq!(errors.len(), 2);
+        assert_eq!((errors[0].location.line, errors[0].location.column), (2, 13));
+        assert_eq!(errors[0].location, errors[1].location);
+    }
+}
@@ -0,0 +1,174 @@
+use super::{Config, ConfigGroup, Property};
+use std::collections::HashMap;
+use std::env;
+
+/// replace `${name}` and `${env:NAME}` in every string property
+pub fn resolve_vars(config: &mut Config) -> Result<(), String> {
+    // vars can only reference the environment
+    let no_vars = HashMap::new();
+    let mut vars = config.vars.clone();
+    interpolate_group(&mut vars, &no_vars)?;
+    config.vars = vars;
+
+    let vars = config.vars.properties.clone();
+    interpolate_group(&mut config.aliases, &vars)?;
+    interpolate_tables(config, &vars)
+}
+
+/// replace variables in an included file with the main config's vars
+pub fn resolve_included_vars(included: &mut Config, vars: &ConfigGroup) -> Result<(), String> {
+    interpolate_tables(included, &vars.properties)
+}
+
+fn interpolate_tables(
+    config: &mut Config,
+    vars: &HashMap<String, Property>,
+) -> Result<(), String> {
+    interpolate_group(&mut config.global, vars)?;
+    for group in config.bars.iter_mut()
+        .chain(config.floats.iter_mut())
+        .chain(config.components.iter_mut())
+    {
+        interpolate_group(group, vars)?;
+    }
+    Ok(())
+}
+
+fn interpolate_group(
+    group: &mut ConfigGroup,
+    vars: &HashMap<String, Property>,
+) -> Result<(), String> {
+    let ConfigGroup { name, properties, location, locations } = group;
+    for (prop, value) in properties.iter_mut() {
+        *value = interpolate_property(value, vars).map_err(|err| {
+            let location = locations.get(prop).unwrap_or(location);
+            format!("{}: {} in {} of #{}", location, err, prop, name)
+        })?;
+    }
+    Ok(())
+}
+
+fn interpolate_property(
+    property: &Property,
+    vars: &HashMap<String, Property>,
+) -> Result<Property, String> {
+    match property {
+        Property::String(input) => interpolate_string(input, vars),
+        Property::Array(arr) => arr
+            .iter()
+            .map(|item| interpolate_property(item, vars))
+            .collect::<Result<Vec<Property>, String>>()
+            .map(Property::Array),
+        Property::Object(obj) => {
+            let mut properties = HashMap::new();
+            for (key, value) in obj.iter() {
+                properties.insert(key.to_string(), interpolate_property(value, vars)?);
+            }
+            Ok(Property::Object(properties))
+        },
+        _ => Ok(property.clone()),
+    }
+}
+
+fn interpolate_string(
+    input: &str,
+    vars: &HashMap<String, Property>,
+) -> Result<Property, String> {
+    // a lone variable keeps its type, so arrays can be shared
+    if input.starts_with("${") && input.ends_with('}') {
+        if let Some(value) = vars.get(input[2..input.len() - 1].trim()) {
+            return Ok(value.clone());
+        }
+    }
+
+    let mut output = String::new();
+    let mut rest = input;
+    while let Some(index) = rest.find("${") {
+        // `$${` is a literal `${`
+        if rest[..index].ends_with('$') {
+            output.push_str(&rest[..index - 1]);
+            output.push_str("${");
+            rest = &rest[index + 2..];
+            continue;
+        }
+        output.push_str(&rest[..index]);
+        let end = rest[index..].find('}')
+            .ok_or_else(|| "unterminated variable".to_string())?;
+        let name = rest[index + 2..index + end].trim();
+        output.push_str(&get_var(name, vars)?);
+        rest = &rest[index + end + 1..];
+    }
+    output.push_str(rest);
+
+    Ok(Property::String(output))
+}
+
+fn get_var(name: &str, vars: &HashMap<String, Property>) -> Result<String, String> {
+    if name.starts_with("env:") {
+        let key = &name[4..];
+        return Ok(env::var(key).unwrap_or_else(|_| {
+            warn!("environment variable {} is not set", key);
+            String::new()
+        }));
+    }
+    match vars.get(name) {
+        Some(Property::String(value)) => Ok(value.to_string()),
+        Some(Property::Integer(value)) => Ok(value.to_string()),
+        Some(Property::Float(value)) => Ok(value.to_string()),
+        Some(Property::Boolean(value)) => Ok(value.to_string()),
+        Some(_) => Err(format!("variable {} cannot be used inside a string", name)),
+        None => Err(format!("undefined variable {}", name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_vars() -> HashMap<String, Property> {
+        let mut vars = HashMap::new();
+        vars.insert("accent".to_string(), Property::String("#f0f".to_string()));
+        vars.insert("size".to_string(), Property::Integer(12));
+        vars.insert("ifaces".to_string(), Property::Array(vec![
+            Property::String("eth0".to_string()),
+        ]));
+        vars
+    }
+
+    fn interpolate(input: &str) -> Result<String, String> {
+        match interpolate_string(input, &get_vars())? {
+            Property::String(output) => Ok(output),
+            property => Err(format!("{:?}", property)),
+        }
+    }
+
+    #[test]
+    fn string_interpolation() {
+        assert_eq!(
+            interpolate("<span color='${accent}' size='${ size }'>{}</span>"),
+            Ok("<span color='#f0f' size='12'>{}</span>".to_string()),
+        );
+    }
+    #[test]
+    fn env_interpolation() {
+        env::set_var("CAKEYBAR_TEST_VAR", "value");
+        assert_eq!(interpolate("${env:CAKEYBAR_TEST_VAR}/x"), Ok("value/x".to_string()));
+    }
+    #[test]
+    fn escaped_interpolation() {
+        assert_eq!(interpolate("$${accent} ${accent}"), Ok("${accent} #f0f".to_string()));
+    }
+    #[test]
+    fn typed_interpolation() {
+        match interpolate_string("${ifaces}", &get_vars()) {
+            Ok(Property::Array(arr)) => assert_eq!(arr.len(), 1),
+            other => panic!("{:?}", other),
+        }
+        assert!(interpolate("list: ${ifaces}").is_err());
+    }
+    #[test]
+    fn invalid_interpolation() {
+        assert!(interpolate("${missing}").is_err());
+        assert!(interpolate("${accent").is_err());
+    }
+}
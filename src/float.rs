@@ -7,13 +7,34 @@ use std::cell::RefCell;
 use std::rc::Rc;
 
 use crate::components::{Component, ComponentParams, load_component};
-use crate::config::ConfigGroup;
-use crate::wm::ipc::commands::Selectors;
+use crate::config::{ConfigGroup, Monitor};
 use crate::wm;
 
+options! {
+    pub struct FloatOptions {
+        "class" => class: Option<String> = None,
+        "title" => title: String = "",
+        /// an index, output name, "primary" or "all"
+        "monitor" => monitor: Monitor = 0,
+        /// components to add, identified by name
+        "layout" => layout: Vec<String> = vec![],
+        /// start hidden
+        "hidden" => hidden: bool = false,
+        /// disable shadows in compton
+        "disable-shadow" => disable_shadow: bool = true,
+        /// offsets from the edges of the monitor. setting opposite edges
+        /// offsets from the centre
+        "top" => top: Option<i64> = None,
+        "bottom" => bottom: Option<i64> = None,
+        "left" => left: Option<i64> = None,
+        "right" => right: Option<i64> = None,
+    }
+}
+
 pub struct Float {
     config: ConfigGroup,
-    components: Vec<Box<dyn Component>>,
+    // paired with the name from their config
+    components: Vec<(String, Box<dyn Component>)>,
     overlay: Overlay,
     container: gtk::Box,
     event_ids: Vec<SignalHandlerId>,
@@ -74,6 +95,7 @@ impl Float {
         monitor: &Rectangle,
         existing_window: Option<Window>,
     ) -> Float {
+        let options = FloatOptions::from_config(&config);
         // use existing or create new window
         let is_new = existing_window.is_none();
         let window = if let Some(existing) = existing_window {
@@ -89,7 +111,7 @@ impl Float {
 
         // set base values
         if is_new {
-            window.set_title(config.get_str_or("title", ""));
+            window.set_title(&options.title);
             window.set_type_hint(gdk::WindowTypeHint::PopupMenu);
             window.set_keep_below(true);
             window.set_skip_pager_hint(false);
@@ -123,19 +145,19 @@ impl Float {
 
         // show window (needs to do this at least once)
         window.show_all();
-        if config.get_bool_or("hidden", false) {
+        if options.hidden {
             window.hide();
         }
 
-        if is_new && config.get_bool_or("disable-shadow", true) {
+        if is_new && options.disable_shadow {
             wm::gtk::disable_shadow(&window);
         }
 
         let position = [
-            config.get_int("top").map(|x| x as i32),
-            config.get_int("bottom").map(|x| x as i32),
-            config.get_int("left").map(|x| x as i32),
-            config.get_int("right").map(|x| x as i32),
+            options.top.map(|x| x as i32),
+            options.bottom.map(|x| x as i32),
+            options.left.map(|x| x as i32),
+            options.right.map(|x| x as i32),
         ];
 
         let window = Rc::new(RefCell::new(
@@ -205,7 +227,7 @@ impl Float {
 
     fn unload(&self) {
         // destroy components
-        for component in self.components.iter() {
+        for (_name, component) in self.components.iter() {
             component.destroy();
         }
         // TODO: move into RCWindow
@@ -261,23 +283,40 @@ impl wm::Window for Float {
         self.config.get_int_or("monitor", 0) as _
     }
 
+    fn get_config(&self) -> &ConfigGroup {
+        &self.config
+    }
+
+    fn get_type(&self) -> &'static str {
+        "float"
+    }
+
+    fn is_visible(&self) -> bool {
+        self.window.borrow().gtkwindow.is_visible()
+    }
+
     fn add_component(&mut self, component: Box<dyn Component>) {
-        self.components.push(component);
+        self.components.push((String::new(), component));
     }
 
     fn load_component(&mut self, config: ConfigGroup, container: &gtk::Box, wm_util: &wm::WMUtil) {
+        let name = config.name.clone();
         load_component(ComponentParams {
             container,
             config,
             window: Box::new(self),
             wm_util,
         });
+        // nested components have already been named
+        self.components.iter_mut()
+            .filter(|(component_name, _)| component_name.is_empty())
+            .for_each(|(component_name, _)| *component_name = name.clone());
     }
 
-    fn matches_selectors(&self, selectors: &Selectors) -> bool {
-        selectors.contains_id(&self.config.name) || {
-            let class = self.config.get_string("class");
-            class.is_some() && selectors.contains_class(&class.unwrap())
-        }
+    fn refresh_components(&self, names: &[String]) -> usize {
+        self.components.iter()
+            .filter(|(name, _)| names.contains(name))
+            .map(|(_, component)| component.refresh())
+            .count()
     }
 }
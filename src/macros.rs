@@ -16,6 +16,56 @@ macro_rules! clone {
     };
 }
 
+// config
+
+/// define options with their property names, types, defaults and docs in one
+/// place. generates the struct, its default, a `PROPERTIES` schema and
+/// `get_table` to list every option with defaults filled in
+macro_rules! options {
+    (
+        $(#[$attr:meta])*
+        pub struct $name:ident {
+            $(
+                $(#[doc = $doc:literal])*
+                $key:literal => $field:ident: $type_:ty = $default:expr,
+            )*
+        }
+    ) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+        #[serde(default)]
+        pub struct $name {
+            $(
+                $(#[doc = $doc])*
+                #[serde(rename = $key)]
+                pub $field: $type_,
+            )*
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                $name {
+                    $( $field: $default.into(), )*
+                }
+            }
+        }
+
+        impl $name {
+            pub fn from_config(config: &$crate::config::ConfigGroup) -> Self {
+                $crate::config::get_options(config)
+            }
+        }
+
+        pub const PROPERTIES: $crate::config::Schema = &[
+            $( ($key, <$type_ as $crate::config::PropertyType>::TYPE), )*
+        ];
+
+        pub fn get_table(config: &$crate::config::ConfigGroup) -> toml::value::Table {
+            $crate::config::get_table(&$name::from_config(config))
+        }
+    };
+}
+
 // messages
 
 macro_rules! message {
@@ -22,9 +22,49 @@ fn main() {
         return;
     }
 
+    // validate config and theme
+    if args.check {
+        let is_valid = config::check(&args.get_config_path());
+        std::process::exit(if is_valid { 0 } else { 1 });
+    }
+
+    // create a starter config
+    if args.init {
+        let is_created = config::init(&args.get_config_path());
+        std::process::exit(if is_created { 0 } else { 1 });
+    }
+
+    // print resolved config
+    if let Some(format) = args.print_config.as_ref() {
+        let is_valid = config::print_config(&args.get_config_path(), format);
+        std::process::exit(if is_valid { 0 } else { 1 });
+    }
+
+    // list instances with a running IPC socket
+    if args.list_instances {
+        for instance in wm::ipc::get_instances() {
+            println!("{}", instance);
+        }
+        return;
+    }
+
     // send IPC message
-    if let Some(message) = args.message {
-        wm::ipc::send_message(&message);
+    if let Some(message) = args.message.as_ref() {
+        let instances = if args.all {
+            wm::ipc::get_instances()
+        } else {
+            vec![args.get_instance().to_string()]
+        };
+        if instances.is_empty() {
+            warn!("no instances are running");
+        }
+        for instance in instances {
+            if args.json {
+                wm::ipc::send_json_message(&instance, message);
+            } else {
+                wm::ipc::send_message(&instance, message);
+            }
+        }
         return;
     }
 
@@ -48,19 +88,19 @@ fn main() {
 
 
     application.connect_startup(move |app| {
-        // get config path
-        let config_path = match &args.config {
-            Some(path) => path.to_string(),
-            None => format!("{}/config.toml", *config::CONFIG_DIR),
-        };
-
-        let config_res = config::parse_file(&config_path);
+        let config_res = config::parse_file(&args.get_config_path());
 
         if let Ok(config) = config_res {
+            for err in config.validate() {
+                warn!("{}", err);
+            }
             // start application
             wm::WMUtil::new(app.clone(), config, &args);
         } else if let Err(msg) = config_res {
             error!("{}", msg);
+            if !std::path::Path::new(&args.get_config_path()).exists() {
+                info!("use --init to create a config");
+            }
         }
     });
     application.connect_activate(|_| { });
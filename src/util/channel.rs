@@ -0,0 +1,37 @@
+use glib::translate::ToGlib;
+use glib::{MainContext, PRIORITY_DEFAULT};
+use glib_sys::g_source_remove;
+use gtk;
+
+pub use glib::Sender;
+
+/// the receiving end wakes the main loop, so it can sleep until something is sent
+pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
+    let (s, r) = MainContext::channel(PRIORITY_DEFAULT);
+    (s, Receiver(r))
+}
+
+pub struct Receiver<T>(glib::Receiver<T>);
+
+impl<T: 'static> Receiver<T> {
+    /// call back on the main thread for each message
+    pub fn attach<F>(self, callback: F) -> Listener
+    where
+        F: FnMut(T) -> gtk::Continue + 'static,
+    {
+        let id = self.0.attach(&MainContext::default(), callback);
+        Listener { id: id.to_glib() }
+    }
+}
+
+pub struct Listener {
+    id: u32,
+}
+
+impl Listener {
+    pub fn remove(&self) {
+        unsafe {
+            g_source_remove(self.id);
+        }
+    }
+}
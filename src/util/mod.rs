@@ -1,14 +1,18 @@
 mod label_group;
 mod symbols;
 mod timer;
+mod channel;
 mod programs;
 mod file;
+mod poll;
 
 pub use self::label_group::LabelGroup;
 pub use self::symbols::SymbolFmt;
 pub use self::timer::Timer;
+pub use self::channel::{channel, Listener, Receiver, Sender};
 pub use self::programs::*;
 pub use self::file::*;
+pub use self::poll::{poll_readable, wait_hangup, wakeup, Wakeup};
 
 use std::process::Command;
 
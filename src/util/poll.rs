@@ -0,0 +1,112 @@
+use std::io;
+use std::net::Shutdown;
+use std::os::unix::io::RawFd;
+use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+/// block until some of the fds can be read, or the timeout passes. returns
+/// which ones are readable, counting ones that were closed or hung up
+pub fn poll_readable(fds: &[RawFd], timeout: Option<Duration>) -> io::Result<Vec<bool>> {
+    let mut pollfds = fds.iter()
+        .map(|&fd| libc::pollfd { fd, events: libc::POLLIN, revents: 0 })
+        .collect::<Vec<_>>();
+    let timeout = timeout.map_or(-1, |timeout| {
+        timeout.as_millis().min(i32::MAX as u128) as i32
+    });
+    poll(&mut pollfds, timeout)?;
+    Ok(pollfds.iter().map(|pollfd| pollfd.revents != 0).collect())
+}
+
+/// block until a socket is closed at the other end or shut down at this one.
+/// unlike reading, this keeps waiting after the other end only stops writing
+pub fn wait_hangup(fd: RawFd) -> io::Result<()> {
+    // hang ups are always reported, so there's nothing to ask for
+    let mut pollfds = [libc::pollfd { fd, events: 0, revents: 0 }];
+    poll(&mut pollfds, -1)
+}
+
+fn poll(pollfds: &mut [libc::pollfd], timeout: i32) -> io::Result<()> {
+    loop {
+        let result = unsafe {
+            libc::poll(pollfds.as_mut_ptr(), pollfds.len() as libc::nfds_t, timeout)
+        };
+        if result >= 0 {
+            return Ok(());
+        }
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+}
+
+/// wakes a thread that polls the other end, so it can stop without a timeout
+pub struct Wakeup(UnixStream);
+
+/// the fd to poll becomes readable when woken, or when the wakeup is dropped
+pub fn wakeup() -> io::Result<(Wakeup, UnixStream)> {
+    let (wakeup, woken) = UnixStream::pair()?;
+    Ok((Wakeup(wakeup), woken))
+}
+
+impl Wakeup {
+    pub fn wake(&self) {
+        self.0.shutdown(Shutdown::Both).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::io::AsRawFd;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Instant;
+
+    #[test]
+    fn timeouts() {
+        let (_wakeup, woken) = wakeup().unwrap();
+        let start = Instant::now();
+        let readable = poll_readable(&[woken.as_raw_fd()], Some(Duration::from_millis(20)));
+        assert_eq!(readable.unwrap(), vec![false]);
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn wake_from_another_thread() {
+        let (wakeup, woken) = wakeup().unwrap();
+        let (_other, other_woken) = self::wakeup().unwrap();
+        let handle = thread::spawn(move || {
+            poll_readable(&[other_woken.as_raw_fd(), woken.as_raw_fd()], None).unwrap()
+        });
+        thread::sleep(Duration::from_millis(20));
+        wakeup.wake();
+        assert_eq!(handle.join().unwrap(), vec![false, true]);
+    }
+
+    #[test]
+    fn hangups() {
+        let (ours, theirs) = UnixStream::pair().unwrap();
+        theirs.shutdown(Shutdown::Write).unwrap();
+        let (done_s, done_r) = mpsc::channel();
+        thread::spawn(move || done_s.send(wait_hangup(ours.as_raw_fd()).is_ok()));
+        // only writing was shut down, so it's still waiting
+        assert!(done_r.recv_timeout(Duration::from_millis(20)).is_err());
+        drop(theirs);
+        assert_eq!(done_r.recv(), Ok(true));
+
+        let (ours, theirs) = UnixStream::pair().unwrap();
+        let reader = ours.try_clone().unwrap();
+        let handle = thread::spawn(move || wait_hangup(reader.as_raw_fd()).unwrap());
+        ours.shutdown(Shutdown::Both).unwrap();
+        handle.join().unwrap();
+        drop(theirs);
+    }
+
+    #[test]
+    fn wake_on_drop() {
+        let (wakeup, woken) = wakeup().unwrap();
+        drop(wakeup);
+        assert_eq!(poll_readable(&[woken.as_raw_fd()], None).unwrap(), vec![true]);
+    }
+}
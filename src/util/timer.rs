@@ -3,32 +3,48 @@ use glib::translate::ToGlib;
 use glib_sys::g_source_remove;
 use gtk;
 
-pub struct Timer(u32);
+use std::cell::RefCell;
+use std::rc::Rc;
+
+type Callback = Rc<RefCell<dyn FnMut() -> gtk::Continue>>;
+
+pub struct Timer {
+    id: u32,
+    callback: Callback,
+}
 
 impl Timer {
-    pub fn add_seconds<F>(interval: u32, mut callback: F) -> Self
+    pub fn add_seconds<F>(interval: u32, callback: F) -> Self
     where
         F: FnMut() -> gtk::Continue + 'static,
     {
-        callback();
-        Self::from(gtk::timeout_add_seconds(interval, callback))
+        let callback = Self::init_callback(callback);
+        let id = gtk::timeout_add_seconds(interval, clone!(callback move || {
+            (&mut *callback.borrow_mut())()
+        }));
+        Self::from(id, callback)
     }
 
-    pub fn add_ms<F>(interval: u32, mut callback: F) -> Self
+    fn init_callback<F>(mut callback: F) -> Callback
     where
         F: FnMut() -> gtk::Continue + 'static,
     {
         callback();
-        Self::from(gtk::timeout_add(interval, callback))
+        Rc::new(RefCell::new(callback))
+    }
+
+    fn from(src: SourceId, callback: Callback) -> Self {
+        Timer { id: src.to_glib(), callback }
     }
 
-    pub fn from(src: SourceId) -> Self {
-        Timer(src.to_glib())
+    /// run the callback now, without waiting for the interval
+    pub fn tick(&self) {
+        (&mut *self.callback.borrow_mut())();
     }
 
     pub fn remove(&self) {
         unsafe {
-            g_source_remove(self.0);
+            g_source_remove(self.id);
         }
     }
 }
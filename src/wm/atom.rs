@@ -1,4 +1,3 @@
-use std::cell::RefCell;
 use std::collections::HashMap;
 use xcb;
 
@@ -6,6 +5,7 @@ macro_rules! atoms {
     ( $( $x:ident ),*, ) => {
         #[allow(non_snake_case)]
         $(pub const $x: &'static str = stringify!($x);)*
+        const ALL: &[&str] = &[$($x),*];
     }
 }
 
@@ -19,6 +19,10 @@ atoms!(
     _NET_WM_WINDOW_TYPE_DOCK,
     _NET_ACTIVE_WINDOW,
     _NET_CURRENT_DESKTOP,
+    _NET_NUMBER_OF_DESKTOPS,
+    _NET_DESKTOP_NAMES,
+    _NET_DESKTOP_VIEWPORT,
+    _NET_SUPPORTING_WM_CHECK,
     _NET_WM_VISIBLE_NAME,
     _NET_WM_NAME,
     _NET_WM_STATE,
@@ -37,32 +41,34 @@ atoms!(
     WM_PROTOCOLS,
     _COMPTON_SHADOW,
     MANAGER,
+    UTF8_STRING,
 );
 
-pub struct Atoms<'a> {
-    conn: &'a xcb::Connection,
-    cache: RefCell<HashMap<String, xcb::Atom>>,
+/// every atom is interned up front, in one round-trip
+pub struct Atoms {
+    atoms: HashMap<&'static str, xcb::Atom>,
 }
 
-impl<'a> Atoms<'a> {
+impl Atoms {
     pub fn new(conn: &xcb::Connection) -> Atoms {
-        Atoms {
-            conn: conn,
-            cache: RefCell::new(HashMap::new()),
-        }
+        let cookies = ALL.iter()
+            .map(|name| (*name, xcb::intern_atom(conn, false, name)))
+            .collect::<Vec<_>>();
+        let atoms = cookies.into_iter()
+            .filter_map(|(name, cookie)| {
+                cookie.get_reply().ok().map(|reply| (name, reply.atom()))
+            })
+            .collect();
+        Atoms { atoms }
     }
 
     pub fn get(&self, name: &str) -> xcb::Atom {
-        let mut cache = self.cache.borrow_mut();
-        if cache.contains_key(name) {
-            *cache.get(name).unwrap()
-        } else {
-            let atom = xcb::intern_atom(self.conn, false, name)
-                .get_reply()
-                .unwrap()
-                .atom();
-            cache.insert(name.to_string(), atom);
-            atom
+        match self.atoms.get(name) {
+            Some(atom) => *atom,
+            None => {
+                warn!("atom {} was not interned", name);
+                xcb::ATOM_NONE
+            },
         }
     }
 }
@@ -1,15 +1,15 @@
 use gtk;
 
-use std::sync::mpsc;
 use std::thread;
 
 use std::io::Read; // Error, Write,
 
+use crate::util;
 use crate::wm::bsp;
 use crate::wm::events::{Event, EventValue};
 
 pub fn listen(wm_util: &crate::wm::WMUtil) {
-    let (tx, rx) = mpsc::channel();
+    let (tx, rx) = util::channel();
 
     thread::spawn(move || match bsp::connect() {
         Ok(mut stream) => {
@@ -39,30 +39,28 @@ pub fn listen(wm_util: &crate::wm::WMUtil) {
         }
     });
 
-    gtk::timeout_add(10, clone!(wm_util move || {
-        while let Ok(msg_result) = rx.try_recv() {
-            match msg_result {
-                Ok(msg) => {
-                    if let Ok(msg) = msg {
-                        if msg.starts_with("W") {
-                            let workspaces = bsp::parse_workspaces(msg);
-                            wm_util.emit_value(
-                                Event::Workspace,
-                                EventValue::Workspaces(workspaces),
-                            );
-                        }
+    rx.attach(clone!(wm_util move |msg_result| {
+        match msg_result {
+            Ok(msg) => {
+                if let Ok(msg) = msg {
+                    if msg.starts_with("W") {
+                        let workspaces = bsp::parse_workspaces(msg);
+                        wm_util.emit_value(
+                            Event::Workspace,
+                            EventValue::Workspaces(workspaces),
+                        );
                     }
-                },
-                Err(err) => {
-                    warn!("{}, restarting thread", err.to_lowercase());
-                    gtk::timeout_add(1000, clone!(wm_util move || {
-                        listen(&wm_util);
-                        gtk::Continue(false)
-                    }));
-                    return gtk::Continue(false);
-                },
-            };
-        }
+                }
+            },
+            Err(err) => {
+                warn!("{}, restarting thread", err.to_lowercase());
+                gtk::timeout_add(1000, clone!(wm_util move || {
+                    listen(&wm_util);
+                    gtk::Continue(false)
+                }));
+                return gtk::Continue(false);
+            },
+        };
         gtk::Continue(true)
     }));
 }
@@ -2,20 +2,28 @@ use crate::wm::workspace::Workspace;
 
 // data
 
-#[derive(Debug, Hash, Eq, PartialEq)]
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
 pub enum Event {
     WindowTitle,
     Windows,
     Mode,
     Workspace,
     Focus(String),
+    /// content for an ipc-label
+    Set(String),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(untagged)]
 pub enum EventValue {
     String(String),
     Workspaces(Vec<Workspace>),
-    Windows(HashMap<xcb::Window, crate::wm::xcb::xwindows::XWindowData>)
+    Windows(HashMap<xcb::Window, crate::wm::xcb::xwindows::XWindowData>),
+    Content {
+        markup: Option<String>,
+        tooltip: Option<String>,
+        classes: Vec<String>,
+    },
 }
 
 // impl
@@ -2,7 +2,14 @@ use gdk::{Display, DisplayExt, MonitorExt, Screen};
 use glib::Error;
 use gtk::{CssProvider, CssProviderExt, Rectangle, StyleContext};
 
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use crate::wm::WMUtil;
+
+mod widget;
 mod window;
+pub use self::widget::*;
 pub use self::window::*;
 
 pub fn css_reset() {
@@ -33,6 +40,30 @@ pub fn load_theme(path: &str) -> Result<CssProvider, Error> {
     }
 }
 
+/// parse a theme without applying it, collecting every error
+pub fn get_theme_errors(path: &str) -> Vec<String> {
+    let provider = CssProvider::new();
+    let errors = Rc::new(RefCell::new(Vec::new()));
+    let file = path.to_string();
+    provider.connect_parsing_error(clone!(errors move |_, section, err| {
+        // lines and columns are zero indexed
+        errors.borrow_mut().push(format!(
+            "{}:{}:{}: {}",
+            file,
+            section.get_start_line() + 1,
+            section.get_start_position() + 1,
+            err,
+        ));
+    }));
+    if let Err(err) = provider.load_from_path(path) {
+        if errors.borrow().is_empty() {
+            errors.borrow_mut().push(format!("{}: {}", path, err));
+        }
+    }
+    let errors = errors.borrow().clone();
+    errors
+}
+
 pub fn unload_theme(provider: &CssProvider) {
     let screen = Screen::get_default().unwrap();
     StyleContext::remove_provider_for_screen(&screen, provider);
@@ -63,6 +94,51 @@ pub fn get_monitor_coords() -> Vec<(i32, i32, String)> {
     monitors
 }
 
+/// indexes of monitors matching an output name, "primary" or "all"
+pub fn get_monitor_indices(query: &str) -> Vec<i32> {
+    let display = match Display::get_default() {
+        Some(display) => display,
+        None => return Vec::new(),
+    };
+    let primary = display.get_primary_monitor();
+    let indices = (0..display.get_n_monitors())
+        .filter(|&i| {
+            display.get_monitor(i).map_or(false, |monitor| match query {
+                "all" => true,
+                "primary" => primary.as_ref() == Some(&monitor),
+                name => monitor.get_model().map_or(false, |model| model == name),
+            })
+        })
+        .collect::<Vec<i32>>();
+    // not every setup has a primary monitor
+    if indices.is_empty() && query == "primary" && display.get_n_monitors() > 0 {
+        vec![0]
+    } else {
+        indices
+    }
+}
+
+/// rebuild windows when monitors are added, removed or reconfigured
+pub fn listen_monitors(wm_util: &WMUtil) {
+    let screen = match Screen::get_default() {
+        Some(screen) => screen,
+        None => return,
+    };
+    // xrandr changes fire several signals, so wait for them to settle
+    let generation = Rc::new(Cell::new(0u32));
+    screen.connect_monitors_changed(clone!((wm_util, generation) move |_| {
+        let current = generation.get().wrapping_add(1);
+        generation.set(current);
+        gtk::timeout_add(500, clone!((wm_util, generation) move || {
+            if generation.get() == current {
+                info!("monitors changed");
+                wm_util.load_windows();
+            }
+            gtk::Continue(false)
+        }));
+    }));
+}
+
 pub fn get_monitor_name(monitor_index: i32) -> Option<String> {
     let display = Display::get_default()?;
     let monitor = display.get_monitor(monitor_index)?;
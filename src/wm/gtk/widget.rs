@@ -0,0 +1,121 @@
+use gtk::prelude::*;
+use gtk::{Container, Label, Widget};
+
+/// walking widgets, so component lookups can be tested without a display
+pub trait WidgetTree: Clone + PartialEq {
+    fn get_widget_name(&self) -> Option<String>;
+    fn get_widget_children(&self) -> Vec<Self>;
+}
+
+impl WidgetTree for Widget {
+    fn get_widget_name(&self) -> Option<String> {
+        WidgetExt::get_name(self).map(|name| name.to_string())
+    }
+
+    fn get_widget_children(&self) -> Vec<Self> {
+        match self.downcast_ref::<Container>() {
+            Some(container) => container.get_children(),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// each component in the tree with the components containing it, outermost
+/// first. windows name their own widgets too, so those are skipped
+pub fn find_components<T: WidgetTree>(
+    widget: &T,
+    window_widgets: &[T],
+    names: &[String],
+) -> Vec<Vec<(String, T)>> {
+    let mut found = Vec::new();
+    find_components_in(widget, window_widgets, names, &mut Vec::new(), &mut found);
+    found
+}
+
+fn find_components_in<T: WidgetTree>(
+    widget: &T,
+    window_widgets: &[T],
+    names: &[String],
+    path: &mut Vec<(String, T)>,
+    found: &mut Vec<Vec<(String, T)>>,
+) {
+    let name = widget.get_widget_name()
+        .filter(|name| names.contains(name) && !window_widgets.contains(widget));
+    if let Some(name) = &name {
+        path.push((name.clone(), widget.clone()));
+        found.push(path.clone());
+    }
+    for child in widget.get_widget_children() {
+        find_components_in(&child, window_widgets, names, path, found);
+    }
+    if name.is_some() {
+        path.pop();
+    }
+}
+
+/// text currently shown by a label, or by every label inside a container
+pub fn get_widget_text(widget: &Widget) -> String {
+    if let Some(label) = widget.downcast_ref::<Label>() {
+        return label.get_text().map(|text| text.to_string()).unwrap_or_default();
+    }
+    match widget.downcast_ref::<Container>() {
+        Some(container) => container
+            .get_children()
+            .iter()
+            .map(get_widget_text)
+            .filter(|text| !text.is_empty())
+            .collect::<Vec<String>>()
+            .join(" "),
+        None => String::new(),
+    }
+}
+
+pub fn get_style_classes(widget: &Widget) -> Vec<String> {
+    match widget.get_style_context() {
+        Some(ctx) => ctx.list_classes().iter().map(|class| class.to_string()).collect(),
+        None => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Node(usize, &'static str, Vec<Node>);
+
+    impl WidgetTree for Node {
+        fn get_widget_name(&self) -> Option<String> {
+            Some(self.1.to_string()).filter(|name| !name.is_empty())
+        }
+
+        fn get_widget_children(&self) -> Vec<Self> {
+            self.2.clone()
+        }
+    }
+
+    fn get_paths(found: Vec<Vec<(String, Node)>>) -> Vec<Vec<(String, usize)>> {
+        found.into_iter()
+            .map(|path| path.into_iter().map(|(name, node)| (name, node.0)).collect())
+            .collect()
+    }
+
+    #[test]
+    fn components_named_like_their_window() {
+        let container = Node(1, "clock", vec![
+            Node(2, "group", vec![
+                Node(3, "clock", vec![Node(4, "", vec![])]),
+                Node(5, "label", vec![]),
+            ]),
+            Node(6, "title", vec![]),
+        ]);
+        let root = Node(0, "", vec![container.clone()]);
+        let names = vec!["clock".to_string(), "group".to_string(), "title".to_string()];
+        let found = find_components(&root, &[container], &names);
+        assert_eq!(get_paths(found), vec![
+            vec![("group".to_string(), 2)],
+            vec![("group".to_string(), 2), ("clock".to_string(), 3)],
+            vec![("title".to_string(), 6)],
+        ]);
+    }
+}
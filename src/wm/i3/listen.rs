@@ -1,11 +1,11 @@
 use gtk;
 use i3ipc::event::Event as I3Event;
 use i3ipc::{I3EventListener, Subscription};
+use crate::util;
 use crate::wm::events::{Event, EventValue};
 use crate::wm::i3;
 use crate::wm::workspace::Workspace;
 
-use std::sync::mpsc;
 use std::thread;
 
 enum I3Msg {
@@ -14,7 +14,7 @@ enum I3Msg {
 }
 
 pub fn listen(wm_util: &crate::wm::WMUtil) {
-    let (tx, rx) = mpsc::channel();
+    let (tx, rx) = util::channel();
 
     thread::spawn(move || {
         let listener_result = I3EventListener::connect();
@@ -61,35 +61,33 @@ pub fn listen(wm_util: &crate::wm::WMUtil) {
         };
     });
 
-    gtk::timeout_add(10, clone!(wm_util move || {
-        while let Ok(msg_result) = rx.try_recv() {
-            match msg_result {
-                Ok(msg) => {
-                    match msg {
-                        I3Msg::Mode(value) => {
-                            wm_util.emit_value(
-                                Event::Mode,
-                                EventValue::String(value),
-                            );
-                        },
-                        I3Msg::Workspace(value) => {
-                            wm_util.emit_value(
-                                Event::Workspace,
-                                EventValue::Workspaces(value),
-                            );
-                        },
-                    }
-                },
-                Err(err) => {
-                    warn!("{}, restarting thread", err.to_lowercase());
-                    gtk::timeout_add(1000, clone!(wm_util move || {
-                        listen(&wm_util);
-                        gtk::Continue(false)
-                    }));
-                    return gtk::Continue(false);
-                },
-            };
-        }
+    rx.attach(clone!(wm_util move |msg_result| {
+        match msg_result {
+            Ok(msg) => {
+                match msg {
+                    I3Msg::Mode(value) => {
+                        wm_util.emit_value(
+                            Event::Mode,
+                            EventValue::String(value),
+                        );
+                    },
+                    I3Msg::Workspace(value) => {
+                        wm_util.emit_value(
+                            Event::Workspace,
+                            EventValue::Workspaces(value),
+                        );
+                    },
+                }
+            },
+            Err(err) => {
+                warn!("{}, restarting thread", err.to_lowercase());
+                gtk::timeout_add(1000, clone!(wm_util move || {
+                    listen(&wm_util);
+                    gtk::Continue(false)
+                }));
+                return gtk::Continue(false);
+            },
+        };
         gtk::Continue(true)
     }));
 }
@@ -1,33 +1,78 @@
+use crate::wm::events::Event;
+
 pub enum Command {
     ReloadConfig(Option<String>),
     ReloadTheme(Option<String>),
     Show(Selectors),
     Hide(Selectors),
-    Focus(Selector),
+    Toggle(Selectors),
+    Refresh(Selectors),
+    AddClass(Selectors, String),
+    RemoveClass(Selectors, String),
+    ToggleClass(Selectors, String),
+    Focus(Selectors),
+    Set(String, Vec<String>, Option<String>),
+    Tooltip(String, String),
+    Workspace(WorkspaceTarget),
+    Exec(String),
+    Watch(bool),
+    WatchStatus,
+    Subscribe(Vec<Event>),
+    Get(Query),
 }
 
-pub struct Selectors(pub Vec<Selector>);
+/// a command in a sequence and when to run it
+pub struct Step {
+    pub input: String,
+    pub command: Command,
+    pub condition: Condition,
+}
 
-impl Selectors {
-    pub fn contains_id(&self, name: &str) -> bool {
-        self.0.contains(&Selector::Id(name.to_owned()))
-    }
-    pub fn contains_class(&self, name: &str) -> bool {
-        self.0.contains(&Selector::Class(name.to_owned()))
-    }
+#[derive(Debug, PartialEq)]
+pub enum Condition {
+    /// after `;` or at the start
+    Always,
+    /// after `&&`, only if the last command succeeded
+    Success,
 }
 
-#[derive(PartialEq)]
-pub enum Selector {
-    Class(String),
-    Id(String),
+/// a workspace name, or next/prev with an optional monitor
+pub enum WorkspaceTarget {
+    Name(String),
+    Next(Option<String>),
+    Prev(Option<String>),
+}
+
+pub enum Query {
+    Windows,
+    Components,
+    Config,
+    Workspaces,
+    Component(Selectors),
 }
 
-impl Selector {
-    pub fn get_name(&self) -> String {
-        match self {
-            Selector::Id(name) => name.to_string(),
-            Selector::Class(name) => name.to_string(),
-        }
-    }
+/// alternatives separated by commas
+pub struct Selectors(pub Vec<Selector>);
+
+/// compounds each inside the one before, like `#left .stat` or `#left > .stat`.
+/// the first compound is a descendant of the root
+pub struct Selector(pub Vec<(Combinator, Compound)>);
+
+/// how a compound is related to the one before it
+#[derive(Clone, Copy, PartialEq)]
+pub enum Combinator {
+    /// anywhere inside, separated by whitespace
+    Descendant,
+    /// directly inside, separated by `>`
+    Child,
+}
+
+/// simple selectors that all have to match, like `#bar.focused`
+pub struct Compound(pub Vec<Simple>);
+
+/// names and values can use `*` and `?` wildcards
+pub enum Simple {
+    Id(String),
+    Class(String),
+    Attribute(String, String),
 }
@@ -0,0 +1,338 @@
+use crate::util;
+use crate::wm::ipc::commands::*;
+use crate::wm::ipc::listen::{dispatch, get_response, get_subscription, parse, start_subscription, Message};
+use crate::wm::ipc::parser::parse_command;
+use crate::wm::ipc::protocol::ResponseBody;
+use crate::wm::events::Event;
+
+use dbus::blocking::Connection;
+use dbus::blocking::stdintf::org_freedesktop_dbus::RequestNameReply;
+use dbus::channel::{BusType, Channel, MatchingReceiver, Sender};
+use dbus::message::MatchRule;
+use dbus_crossroads::{Crossroads, IfaceBuilder, MethodErr};
+use crossbeam_channel::{self as channel, select};
+use serde_json::Value;
+use std::os::unix::io::RawFd;
+use std::thread;
+use std::time::Duration;
+
+// the same commands as the socket, as methods on the session bus
+
+pub const PATH: &str = "/org/cakeybar";
+pub const INTERFACE: &str = "org.cakeybar";
+pub const PARSE_ERROR: &str = "org.cakeybar.Error.Parse";
+pub const COMMAND_ERROR: &str = "org.cakeybar.Error.Command";
+
+type IPCSender = util::Sender<Message>;
+
+pub fn listen(instance: &str, s: IPCSender) {
+    let name = get_bus_name(instance);
+    thread::spawn(move || {
+        let result = Channel::get_private(BusType::Session)
+            .map_err(|err| err.to_string())
+            .and_then(|channel| serve(channel, &name, s));
+        if let Err(err) = result {
+            error!("D-Bus {}", err);
+        }
+    });
+}
+
+/// org.cakeybar for the default instance, org.cakeybar.<name> for others
+pub fn get_bus_name(instance: &str) -> String {
+    if instance == "default" {
+        return INTERFACE.to_string();
+    }
+    let element = instance
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    // elements can't start with a digit
+    if element.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("{}._{}", INTERFACE, element)
+    } else {
+        format!("{}.{}", INTERFACE, element)
+    }
+}
+
+pub fn serve(mut channel: Channel, name: &str, s: IPCSender) -> Result<(), String> {
+    // the fd is polled instead of processing on a timeout
+    channel.set_watch_enabled(true);
+    let conn = Connection::from(channel);
+    let events = vec![Event::Workspace, Event::WindowTitle, Event::Mode];
+    let subscription = start_subscription(&s, "D-Bus signals".to_string(), events)?;
+
+    let mut cr = Crossroads::new();
+    let iface = cr.register(INTERFACE, add_members);
+    cr.insert(PATH, &[iface], s);
+
+    let reply = conn.request_name(name, false, true, true).map_err(|err| err.to_string())?;
+    if reply != RequestNameReply::PrimaryOwner {
+        return Err(format!("{} is already taken", name));
+    }
+    info!("D-Bus using {}", name);
+
+    conn.start_receive(MatchRule::new_method_call(), Box::new(move |msg, conn| {
+        cr.handle_message(msg, conn).ok();
+        true
+    }));
+
+    // wait for the bus to be readable on another thread, so both can be selected on
+    let (readable_s, readable_r) = channel::bounded(0);
+    let (read_s, read_r) = channel::bounded(0);
+    let fd = conn.channel().watch().fd;
+    thread::spawn(move || watch_fd(fd, readable_s, read_r));
+
+    // libdbus can buffer messages while reading others
+    let process = || -> Result<(), String> {
+        while conn.process(Duration::from_millis(0)).map_err(|err| err.to_string())? {}
+        Ok(())
+    };
+    process()?;
+    loop {
+        select! {
+            recv(readable_r) -> readable => {
+                readable.map_err(|_| "stopped reading the bus".to_string())?;
+                process()?;
+                read_s.send(()).ok();
+            },
+            recv(subscription.events) -> payload => {
+                let payload = payload.map_err(|_| "lost the main thread".to_string())?;
+                if let Some(signal) = get_signal(&payload) {
+                    conn.send(signal).map_err(|_| "cannot send signal".to_string())?;
+                    conn.channel().flush();
+                    process()?;
+                }
+            },
+        }
+    }
+}
+
+/// tell the serving thread when the bus can be read, and wait for it to be
+fn watch_fd(fd: RawFd, readable: channel::Sender<()>, read: channel::Receiver<()>) {
+    loop {
+        if let Err(err) = util::poll_readable(&[fd], None) {
+            error!("D-Bus {}", err);
+            break;
+        }
+        if readable.send(()).is_err() || read.recv().is_err() {
+            break;
+        }
+    }
+}
+
+fn add_members(b: &mut IfaceBuilder<IPCSender>) {
+    b.method("Run", ("message",), ("result",), |_, s, (message,): (String,)| {
+        run_message(s, message).map(|result| (result,))
+    });
+    b.method("Get", ("query",), ("result",), |_, s, (query,): (String,)| {
+        run(s, format!("get {}", query)).map(|result| (result.to_string(),))
+    });
+    b.method("Show", ("selectors",), (), |_, s, (selectors,): (String,)| {
+        run(s, format!("show {}", selectors)).map(|_| ())
+    });
+    b.method("Hide", ("selectors",), (), |_, s, (selectors,): (String,)| {
+        run(s, format!("hide {}", selectors)).map(|_| ())
+    });
+    b.method("Toggle", ("selectors",), (), |_, s, (selectors,): (String,)| {
+        run(s, format!("toggle {}", selectors)).map(|_| ())
+    });
+    b.method("Refresh", ("selectors",), (), |_, s, (selectors,): (String,)| {
+        run(s, format!("refresh {}", selectors)).map(|_| ())
+    });
+    b.method("AddClass", ("selectors", "class"), (), |_, s, (selectors, class): (String, String)| {
+        run(s, format!("addclass {} {}", selectors, class)).map(|_| ())
+    });
+    b.method("RemoveClass", ("selectors", "class"), (), |_, s, (selectors, class): (String, String)| {
+        run(s, format!("removeclass {} {}", selectors, class)).map(|_| ())
+    });
+    b.method("ToggleClass", ("selectors", "class"), (), |_, s, (selectors, class): (String, String)| {
+        run(s, format!("toggleclass {} {}", selectors, class)).map(|_| ())
+    });
+    b.method("Focus", ("selector",), (), |_, s, (selector,): (String,)| {
+        run(s, format!("focus {}", selector)).map(|_| ())
+    });
+    b.method(
+        "Set",
+        ("name", "classes", "markup"),
+        (),
+        |_, s, (name, classes, markup): (String, Vec<String>, String)| {
+            let classes = classes.iter().map(|class| format!(" .{}", class)).collect::<String>();
+            run(s, format!("set #{}{} -- {}", name, classes, markup)).map(|_| ())
+        },
+    );
+    b.method("Tooltip", ("name", "markup"), (), |_, s, (name, markup): (String, String)| {
+        run(s, format!("tooltip #{} {}", name, markup)).map(|_| ())
+    });
+    b.method("Workspace", ("target",), (), |_, s, (target,): (String,)| {
+        run(s, format!("workspace {}", target)).map(|_| ())
+    });
+    b.method("Exec", ("command",), (), |_, s, (command,): (String,)| {
+        run(s, format!("exec {}", command)).map(|_| ())
+    });
+    b.method("ReloadConfig", ("path",), (), |_, s, (path,): (String,)| {
+        run(s, format!("reload config {}", path)).map(|_| ())
+    });
+    b.method("ReloadTheme", ("path",), (), |_, s, (path,): (String,)| {
+        run(s, format!("reload theme {}", path)).map(|_| ())
+    });
+    b.signal::<(String,), _>("WorkspacesChanged", ("workspaces",));
+    b.signal::<(String,), _>("WindowTitleChanged", ("title",));
+    b.signal::<(String,), _>("ModeChanged", ("mode",));
+}
+
+/// a single command built from method arguments, so never split or expanded
+fn run(s: &IPCSender, input: String) -> Result<Value, MethodErr> {
+    let command = parse_command(&input).map_err(|err| MethodErr::from((PARSE_ERROR, err)))?;
+    let steps = vec![Step { input: input.clone(), command, condition: Condition::Always }];
+    let mut outcomes = dispatch(s, input, steps)
+        .map_err(|err| MethodErr::from((COMMAND_ERROR, err)))?;
+    match outcomes.remove(0).result {
+        Some(Ok(result)) => Ok(result),
+        Some(Err(err)) => Err((COMMAND_ERROR, err).into()),
+        None => Ok(Value::Null),
+    }
+}
+
+/// a message in the IPC syntax, replying with JSON like the socket does
+fn run_message(s: &IPCSender, message: String) -> Result<String, MethodErr> {
+    let steps = parse(s, &message).map_err(|err| MethodErr::from((PARSE_ERROR, err)))?;
+    if get_subscription(&steps).is_some() {
+        return Err((COMMAND_ERROR, "use the D-Bus signals to subscribe").into());
+    }
+    let outcomes = dispatch(s, message, steps)
+        .map_err(|err| MethodErr::from((COMMAND_ERROR, err)))?;
+    match get_response(Value::Null, outcomes).body {
+        ResponseBody::Ok(result) => Ok(result.to_string()),
+        ResponseBody::Err(err) => Err((COMMAND_ERROR, err.message).into()),
+        ResponseBody::Event(event) => Ok(event.to_string()),
+    }
+}
+
+fn get_signal(payload: &Value) -> Option<dbus::Message> {
+    let member = match payload["name"].as_str()? {
+        "workspace" => "WorkspacesChanged",
+        "title" => "WindowTitleChanged",
+        "mode" => "ModeChanged",
+        _ => return None,
+    };
+    // titles and modes are sent as they are, workspaces as JSON
+    let value = match &payload["value"] {
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    };
+    let signal = dbus::Message::new_signal(PATH, INTERFACE, member).ok()?;
+    Some(signal.append1(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wm::ipc::listen::fake_main_thread;
+    use serde_json::json;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command as Process, Stdio};
+    use std::sync::{Arc, Mutex};
+    use std::time::Instant;
+
+    struct Daemon(Child);
+
+    impl Drop for Daemon {
+        fn drop(&mut self) {
+            self.0.kill().ok();
+        }
+    }
+
+    /// a private bus, so tests don't touch the real session
+    fn start_daemon() -> Option<(Daemon, String)> {
+        let child = Process::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+        let mut daemon = Daemon(child);
+        let mut address = String::new();
+        BufReader::new(daemon.0.stdout.take()?).read_line(&mut address).ok()?;
+        // nothing is printed if it fails to start
+        if address.trim().is_empty() {
+            return None;
+        }
+        Some((daemon, address.trim().to_string()))
+    }
+
+    fn open(address: &str) -> Channel {
+        let mut channel = Channel::open_private(address).unwrap();
+        channel.register().unwrap();
+        channel
+    }
+
+    #[test]
+    fn methods_and_signals() {
+        let (_daemon, address) = match start_daemon() {
+            Some(daemon) => daemon,
+            None => {
+                eprintln!("skipping methods_and_signals, cannot start dbus-daemon");
+                return;
+            },
+        };
+
+        let (sender_s, sender_r) = channel::bounded(1);
+        let (events_s, events_r) = channel::unbounded();
+        thread::spawn(move || fake_main_thread(sender_s, events_s));
+        let s = sender_r.recv_timeout(Duration::from_secs(5)).unwrap();
+        let server_address = address.clone();
+        thread::spawn(move || {
+            serve(open(&server_address), &get_bus_name("test"), s).unwrap();
+        });
+        let events = events_r.recv_timeout(Duration::from_secs(5)).unwrap();
+
+        let conn = Connection::from(open(&address));
+        let proxy = conn.with_proxy("org.cakeybar.test", PATH, Duration::from_secs(5));
+
+        // wait for the name to be taken
+        let start = Instant::now();
+        while proxy.method_call::<(), _, _, _>(INTERFACE, "Show", ("#bar",)).is_err() {
+            assert!(start.elapsed() < Duration::from_secs(5), "server never started");
+            thread::sleep(Duration::from_millis(50));
+        }
+
+        let (result,): (String,) = proxy.method_call(INTERFACE, "Get", ("#clock",)).unwrap();
+        assert_eq!(result, "\"get #clock\"");
+
+        proxy.method_call::<(), _, _, _>(INTERFACE, "Set", ("ci", vec!["failed"], "<b>no</b>"))
+            .unwrap();
+
+        let err = proxy.method_call::<(), _, _, _>(INTERFACE, "Hide", ("#missing",)).unwrap_err();
+        assert_eq!(err.name(), Some(COMMAND_ERROR));
+        assert_eq!(err.message(), Some("nothing matches #missing"));
+
+        let err = proxy.method_call::<(), _, _, _>(INTERFACE, "Show", ("",)).unwrap_err();
+        assert_eq!(err.name(), Some(PARSE_ERROR));
+
+        let (result,): (String,) = proxy.method_call(INTERFACE, "Run", ("show #a; hide #b",)).unwrap();
+        let result: Value = serde_json::from_str(&result).unwrap();
+        assert_eq!(result[1], json!({ "command": "hide #b", "ok": true, "result": "hide #b" }));
+
+        let titles = Arc::new(Mutex::new(Vec::new()));
+        let rule = MatchRule::new_signal(INTERFACE, "WindowTitleChanged");
+        conn.add_match(rule, clone!(titles move |(title,): (String,), _, _| {
+            titles.lock().unwrap().push(title);
+            true
+        })).unwrap();
+
+        events.send(json!({ "name": "title", "value": "vim" })).unwrap();
+        let start = Instant::now();
+        while titles.lock().unwrap().is_empty() && start.elapsed() < Duration::from_secs(5) {
+            conn.process(Duration::from_millis(50)).unwrap();
+        }
+        assert_eq!(*titles.lock().unwrap(), vec!["vim".to_string()]);
+    }
+
+    #[test]
+    fn bus_names() {
+        assert_eq!(get_bus_name("default"), "org.cakeybar");
+        assert_eq!(get_bus_name("dock"), "org.cakeybar.dock");
+        assert_eq!(get_bus_name(":1.0"), "org.cakeybar._1_0");
+        assert_eq!(get_bus_name("2nd"), "org.cakeybar._2nd");
+    }
+}
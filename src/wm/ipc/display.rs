@@ -1,5 +1,6 @@
 use std::fmt;
 use crate::wm::ipc::commands::*;
+use crate::wm::events::Event;
 
 impl fmt::Display for Selectors {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -15,10 +16,29 @@ impl fmt::Display for Selectors {
 
 impl fmt::Display for Selector {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", match self {
-            Selector::Id(name) => format!("#{}", name),
-            Selector::Class(name) => format!(".{}", name),
-        })
+        for (index, (combinator, compound)) in self.0.iter().enumerate() {
+            if index > 0 {
+                write!(f, "{}", match combinator {
+                    Combinator::Descendant => " ",
+                    Combinator::Child => " > ",
+                })?;
+            }
+            write!(f, "{}", compound)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Compound {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for simple in self.0.iter() {
+            match simple {
+                Simple::Id(name) => write!(f, "#{}", name)?,
+                Simple::Class(name) => write!(f, ".{}", name)?,
+                Simple::Attribute(key, value) => write!(f, "[{}={}]", key, value)?,
+            }
+        }
+        Ok(())
     }
 }
 
@@ -31,7 +51,73 @@ impl fmt::Display for Command {
             Command::ReloadTheme(Some(path)) => write!(f, "reloading theme {}", path),
             Command::Show(selectors) => write!(f, "showing {}", selectors),
             Command::Focus(selector) => write!(f, "focus {}", selector),
+            Command::Set(name, _, _) => write!(f, "setting #{}", name),
+            Command::Tooltip(name, _) => write!(f, "setting tooltip of #{}", name),
+            Command::Workspace(WorkspaceTarget::Name(name)) => {
+                write!(f, "focusing workspace {}", name)
+            },
+            Command::Workspace(WorkspaceTarget::Next(monitor)) => {
+                write!(f, "focusing next workspace{}", get_monitor_suffix(monitor))
+            },
+            Command::Workspace(WorkspaceTarget::Prev(monitor)) => {
+                write!(f, "focusing previous workspace{}", get_monitor_suffix(monitor))
+            },
+            Command::Exec(command) => write!(f, "running {}", command),
+            Command::Watch(true) => write!(f, "watching config files"),
+            Command::Watch(false) => write!(f, "stopped watching config files"),
+            Command::WatchStatus => write!(f, "getting watch status"),
             Command::Hide(selectors) => write!(f, "hiding {}", selectors),
+            Command::Toggle(selectors) => write!(f, "toggling {}", selectors),
+            Command::Refresh(selectors) => write!(f, "refreshing {}", selectors),
+            Command::AddClass(selectors, class) => {
+                write!(f, "adding class {} to {}", class, selectors)
+            },
+            Command::RemoveClass(selectors, class) => {
+                write!(f, "removing class {} from {}", class, selectors)
+            },
+            Command::ToggleClass(selectors, class) => {
+                write!(f, "toggling class {} on {}", class, selectors)
+            },
+            Command::Get(query) => write!(f, "get {}", query),
+            Command::Subscribe(events) => write!(f, "subscribed to {}", {
+                events
+                    .iter()
+                    .map(|event| event.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            }),
         }
     }
 }
+
+fn get_monitor_suffix(monitor: &Option<String>) -> String {
+    match monitor {
+        Some(monitor) => format!(" on monitor {}", monitor),
+        None => String::new(),
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Query::Windows => write!(f, "windows"),
+            Query::Components => write!(f, "components"),
+            Query::Config => write!(f, "config"),
+            Query::Workspaces => write!(f, "workspaces"),
+            Query::Component(selector) => write!(f, "{}", selector),
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Event::Workspace => "workspace",
+            Event::WindowTitle => "title",
+            Event::Mode => "mode",
+            Event::Windows => "windows",
+            Event::Focus(_) => "focus",
+            Event::Set(_) => "set",
+        })
+    }
+}
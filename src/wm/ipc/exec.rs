@@ -1,23 +1,245 @@
 use crate::wm::ipc::commands::*;
-use crate::wm::events::Event;
+use crate::wm::events::{Event, EventId, EventValue};
 use crate::wm::WMUtil;
+use crate::wm;
 
-pub fn run_command(wm_util: &WMUtil, cmd: Command) {
+use gtk::prelude::*;
+use crossbeam_channel as channel;
+use serde_json::{json, Value};
+
+/// data to reply with, or an error message
+pub type CommandResult = Result<Value, String>;
+
+pub struct Outcome {
+    pub input: String,
+    pub description: String,
+    /// None if the command was skipped
+    pub result: Option<CommandResult>,
+}
+
+pub fn run_sequence(wm_util: &WMUtil, steps: Vec<Step>) -> Vec<Outcome> {
+    let mut last_ok = true;
+    steps.into_iter().map(|Step { input, command, condition }| {
+        let description = command.to_string();
+        let result = if condition == Condition::Success && !last_ok {
+            None
+        } else {
+            Some(run_command(wm_util, command))
+        };
+        last_ok = result.as_ref().map_or(false, |result| result.is_ok());
+        Outcome { input, description, result }
+    }).collect()
+}
+
+pub fn run_command(wm_util: &WMUtil, cmd: Command) -> CommandResult {
     match cmd {
         Command::ReloadTheme(path_opt) => {
-            wm_util.load_theme(path_opt);
+            wm_util.load_theme(path_opt)?;
         },
         Command::ReloadConfig(path_opt) => {
-            wm_util.reload_config(path_opt);
+            wm_util.reload_config(path_opt)?;
         },
         Command::Show(selectors) => {
-            wm_util.display_windows(&selectors, true);
+            return update_matches(
+                wm_util,
+                &selectors,
+                |window| window.show(),
+                |widget| widget.show(),
+            );
         },
         Command::Hide(selectors) => {
-            wm_util.display_windows(&selectors, false);
+            return update_matches(
+                wm_util,
+                &selectors,
+                |window| window.hide(),
+                |widget| widget.hide(),
+            );
+        },
+        Command::Refresh(selectors) => {
+            // only in the windows that matched, so [monitor=N] picks one copy
+            let count: usize = wm_util
+                .map_components(|window, name, _widget, path| {
+                    if selectors.matches(path) {
+                        Some(window.refresh_components(&[name]))
+                    } else {
+                        None
+                    }
+                })
+                .iter()
+                .sum();
+            if count == 0 {
+                return Err(format!("no components match {}", selectors));
+            }
+        },
+        Command::Toggle(selectors) => {
+            return update_matches(
+                wm_util,
+                &selectors,
+                |window| if window.is_visible() { window.hide() } else { window.show() },
+                |widget| widget.set_visible(!widget.is_visible()),
+            );
+        },
+        Command::AddClass(selectors, class) => {
+            return set_class(wm_util, &selectors, &class, Some(true));
+        },
+        Command::RemoveClass(selectors, class) => {
+            return set_class(wm_util, &selectors, &class, Some(false));
+        },
+        Command::ToggleClass(selectors, class) => {
+            return set_class(wm_util, &selectors, &class, None);
+        },
+        Command::Focus(selectors) => {
+            let components = wm_util.get_matching_components(&selectors);
+            if components.is_empty() {
+                return Err(format!("no components match {}", selectors));
+            }
+            for (name, _widget) in components {
+                wm_util.emit(Event::Focus(name));
+            }
+        },
+        Command::Workspace(WorkspaceTarget::Name(name)) => {
+            wm_util.focus_workspace(&name);
+        },
+        Command::Workspace(WorkspaceTarget::Next(monitor)) => {
+            wm_util.cycle_workspace(true, get_monitor_index(monitor)?);
+        },
+        Command::Workspace(WorkspaceTarget::Prev(monitor)) => {
+            wm_util.cycle_workspace(false, get_monitor_index(monitor)?);
+        },
+        Command::Exec(command) => {
+            crate::util::run_command(command);
+        },
+        Command::Watch(true) => {
+            if wm_util.get_watched_files().is_none() {
+                wm_util.watch_files()?;
+            }
+        },
+        Command::Watch(false) => {
+            wm_util.unwatch_files();
+        },
+        Command::WatchStatus => {
+            let files = wm_util.get_watched_files();
+            return Ok(json!({
+                "watching": files.is_some(),
+                "files": files.unwrap_or_default(),
+            }));
+        },
+        Command::Set(name, classes, markup) => {
+            set_content(wm_util, name, EventValue::Content {
+                markup: Some(markup.unwrap_or_default()),
+                tooltip: None,
+                classes,
+            })?;
         },
-        Command::Focus(selector) => {
-            wm_util.emit(Event::Focus(selector.get_name()));
+        Command::Tooltip(name, tooltip) => {
+            set_content(wm_util, name, EventValue::Content {
+                markup: None,
+                tooltip: Some(tooltip),
+                classes: Vec::new(),
+            })?;
         },
+        Command::Get(query) => {
+            return super::query::run_query(wm_util, query);
+        },
+        Command::Subscribe(_) => {
+            return Err("subscriptions need their own IPC connection".to_string());
+        },
+    }
+    Ok(Value::Null)
+}
+
+/// a monitor index or output name, defaulting to the first monitor
+fn get_monitor_index(monitor: Option<String>) -> Result<i32, String> {
+    match monitor {
+        None => Ok(0),
+        Some(monitor) => monitor.parse::<i32>().or_else(|_| {
+            wm::gtk::get_monitor_indices(&monitor)
+                .first()
+                .cloned()
+                .ok_or_else(|| format!("no monitor matches {}", monitor))
+        }),
+    }
+}
+
+fn set_content(wm_util: &WMUtil, name: String, content: EventValue) -> Result<(), String> {
+    let type_ = wm_util.get_component_config(&name)
+        .and_then(|config| config.get_string("type"));
+    if type_.as_ref().map(String::as_str) != Some("ipc-label") {
+        return Err(format!("#{} is not an ipc-label", name));
+    }
+    wm_util.emit_value(Event::Set(name), content);
+    Ok(())
+}
+
+/// apply changes to matching windows and components
+fn update_matches<W, C>(
+    wm_util: &WMUtil,
+    selectors: &Selectors,
+    on_window: W,
+    on_component: C,
+) -> CommandResult
+where
+    W: Fn(&dyn wm::Window),
+    C: Fn(&gtk::Widget),
+{
+    let windows = wm_util.map_windows(|window| {
+        let matches = window.matches_selectors(selectors);
+        if matches {
+            on_window(window);
+        }
+        matches
+    });
+    let components = wm_util.get_matching_components(selectors);
+    components.iter().for_each(|(_name, widget)| on_component(widget));
+
+    if windows.contains(&true) || !components.is_empty() {
+        Ok(Value::Null)
+    } else {
+        Err(format!("nothing matches {}", selectors))
+    }
+}
+
+/// add, remove or toggle (None) a class
+fn set_class(
+    wm_util: &WMUtil,
+    selectors: &Selectors,
+    class: &str,
+    enabled: Option<bool>,
+) -> CommandResult {
+    let update = |ctx: Option<gtk::StyleContext>| {
+        if let Some(ctx) = ctx {
+            if enabled.unwrap_or_else(|| !ctx.has_class(class)) {
+                ctx.add_class(class);
+            } else {
+                ctx.remove_class(class);
+            }
+        }
+    };
+    update_matches(
+        wm_util,
+        selectors,
+        |window| update(window.get_container().get_style_context()),
+        |widget| update(widget.get_style_context()),
+    )
+}
+
+/// the listeners added for a subscription
+pub type Listeners = Vec<(Event, EventId)>;
+
+/// forward events to the sender until unsubscribed
+pub fn subscribe(wm_util: &WMUtil, events: Vec<Event>, sender: channel::Sender<Value>) -> Listeners {
+    events.into_iter().map(|event| {
+        let name = event.to_string();
+        let id = wm_util.add_listener(event.clone(), clone!(sender move |value| {
+            let payload = json!({ "name": name, "value": value });
+            sender.send(payload).ok();
+        }));
+        (event, id)
+    }).collect()
+}
+
+pub fn unsubscribe(wm_util: &WMUtil, listeners: Listeners) {
+    for (event, id) in listeners {
+        wm_util.remove_listener(event, id);
     }
 }
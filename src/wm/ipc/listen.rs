@@ -1,71 +1,411 @@
 use gtk;
-use crate::wm::ipc;
+use crate::util;
+use crate::wm::ipc::parser::parse_message;
 use crate::wm::ipc::commands::*;
-use crate::wm::ipc::exec::run_command;
+use crate::wm::ipc::exec::{run_sequence, subscribe, unsubscribe, Listeners, Outcome};
+use crate::wm::ipc::protocol::{self, ErrorKind, RequestKind, Response};
+use crate::wm::events::Event;
 use crate::wm::WMUtil;
 
 use crossbeam_channel as channel;
-use std::fs::remove_file;
-use std::io::{Read, Write};
+use gio::prelude::*;
+use serde_json::{json, Value};
+use std::fs::{remove_file, DirBuilder};
+use std::io::{self, Read, Write};
+use std::net::Shutdown;
+use std::os::unix::fs::DirBuilderExt;
+use std::os::unix::io::AsRawFd;
 use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
 use std::thread;
 
-pub fn listen(wm_util: &WMUtil) {
-    let socket_path = crate::config::CAKEYBAR_SOCKET.to_owned();
+pub enum Message {
+    Parse(String, channel::Sender<Result<Vec<Step>, String>>),
+    Command(String, Vec<Step>, channel::Sender<Vec<Outcome>>),
+    Subscribe(String, Vec<Event>, channel::Sender<Value>, channel::Sender<Listeners>),
+    Unsubscribe(Listeners),
+}
+
+pub fn listen(wm_util: &WMUtil, instance: &str, enable_socket: bool, enable_dbus: bool) {
+    if !enable_socket && !enable_dbus {
+        return;
+    }
+    let (s, r) = util::channel();
+    if enable_socket {
+        listen_socket(wm_util, instance, s.clone());
+    }
+    if enable_dbus {
+        super::dbus::listen(instance, s);
+    }
+
+    // receive events
+    r.attach(clone!(wm_util move |message| {
+        match message {
+            Message::Parse(input, reply) => {
+                reply.send(parse_message(&input, &wm_util.get_aliases())).ok();
+            },
+            Message::Command(input, steps, reply) => {
+                info!("received {:?} via IPC...", input);
+                reply.send(run_sequence(&wm_util, steps)).ok();
+            },
+            Message::Subscribe(input, events, sender, reply) => {
+                info!("received {:?} via IPC...", input);
+                reply.send(subscribe(&wm_util, events, sender)).ok();
+            },
+            Message::Unsubscribe(listeners) => {
+                unsubscribe(&wm_util, listeners);
+            },
+        }
+        gtk::Continue(true)
+    }));
+}
+
+fn listen_socket(wm_util: &WMUtil, instance: &str, s: util::Sender<Message>) {
+    let socket_path = crate::config::get_socket_path(instance);
+    // don't take over a socket another instance is using
+    if UnixStream::connect(&socket_path).is_ok() {
+        error!("IPC socket {} is in use (use --instance to name this one)", socket_path);
+        return;
+    }
     // remove from last time
     remove_file(&socket_path).ok();
+    if let Some(dir) = Path::new(&socket_path).parent() {
+        if let Err(err) = DirBuilder::new().recursive(true).mode(0o700).create(dir) {
+            error!("Cannot create {}: {}", dir.display(), err);
+        }
+    }
+
+    let listener = match UnixListener::bind(&socket_path) {
+        Ok(listener) => listener,
+        Err(err) => {
+            error!("Cannot start IPC {}", err);
+            return;
+        },
+    };
+    wm_util.get_app().connect_shutdown(move |_| {
+        remove_file(&socket_path).ok();
+    });
 
     // start listening
-    let (s, r) = channel::unbounded();
-    thread::spawn(move || match UnixListener::bind(&socket_path) {
-        Ok(listener) => {
-            for connection in listener.incoming() {
-                match connection {
-                    Ok(stream) => {
-                        thread::spawn(clone!(s || handle_stream(stream, s)));
-                    }
-                    Err(err) => {
-                        error!("IPC connection error: {}", err);
-                    }
+    thread::spawn(move || {
+        for connection in listener.incoming() {
+            match connection {
+                Ok(stream) => {
+                    thread::spawn(clone!(s || handle_stream(stream, s)));
+                }
+                Err(err) => {
+                    error!("IPC connection error: {}", err);
                 }
             }
         }
-        Err(err) => error!("Cannot start IPC {}", err),
     });
+}
 
-    // receive events
-    gtk::timeout_add(10, clone!(wm_util move || {
-        if let Ok((input, cmd)) = r.try_recv() {
-            info!("received {:?} via IPC...", input);
-            run_command(&wm_util, cmd);
+fn handle_stream(mut stream: UnixStream, s: util::Sender<Message>) {
+    let mut first = [0; 1];
+    if stream.read_exact(&mut first).is_err() {
+        return;
+    }
+    if protocol::is_framed(first[0]) {
+        handle_json(stream, s);
+    } else {
+        handle_text(stream, s, first[0]);
+    }
+}
+
+fn handle_text(mut stream: UnixStream, s: util::Sender<Message>, first: u8) {
+    // the message ends when the client shuts down writing
+    let mut bytes = vec![first];
+    if let Err(err) = stream.read_to_end(&mut bytes) {
+        error!("IPC read error: {}", err);
+        return;
+    }
+    let input = String::from_utf8_lossy(&bytes).trim_end_matches('\0').to_string();
+
+    let response = match parse(&s, &input) {
+        Ok(steps) => {
+            if let Some(events) = get_subscription(&steps) {
+                stream_text_events(stream, &s, input, events);
+                return;
+            }
+            match dispatch(&s, input, steps) {
+                Ok(outcomes) => outcomes
+                    .iter()
+                    .map(|outcome| match &outcome.result {
+                        Some(Ok(Value::Null)) => outcome.description.clone(),
+                        Some(Ok(value)) => value.to_string(),
+                        Some(Err(err)) => format!("e:{}", err),
+                        None => format!("w:skipped {}", outcome.input),
+                    })
+                    .collect::<Vec<String>>()
+                    .join("\n"),
+                Err(err) => format!("e:{}", err),
+            }
         }
-        gtk::Continue(true)
-    }));
+        Err(err) => format!("e:{}", err),
+    };
+    stream.write_all(response.as_bytes()).ok();
+}
+
+fn handle_json(mut stream: UnixStream, s: util::Sender<Message>) {
+    loop {
+        let frame = match protocol::read_frame(&mut stream) {
+            Ok(Some(frame)) => frame,
+            Ok(None) => break,
+            Err(err) => {
+                error!("IPC read error: {}", err);
+                break;
+            }
+        };
+        let response = match protocol::parse_request(&frame) {
+            Ok(request) => match request.kind {
+                RequestKind::Command { command } => {
+                    match parse(&s, &command) {
+                        Ok(steps) => {
+                            if let Some(events) = get_subscription(&steps) {
+                                stream_events(stream, &s, request.id, command, events);
+                                return;
+                            }
+                            match dispatch(&s, command, steps) {
+                                Ok(outcomes) => get_response(request.id, outcomes),
+                                Err(err) => Response::err(request.id, ErrorKind::Command, err),
+                            }
+                        },
+                        Err(err) => Response::err(request.id, ErrorKind::Parse, err),
+                    }
+                }
+            },
+            Err(response) => response,
+        };
+        if let Err(err) = protocol::write_response(&mut stream, &response) {
+            error!("IPC write error: {}", err);
+            break;
+        }
+    }
 }
 
-fn handle_stream(mut stream: UnixStream, s: channel::Sender<(String, Command)>) {
-    let mut buf = [0; 256];
-    stream.read(&mut buf).ok();
-    // convert to string
-    let input = buf
-        .iter()
-        .filter(|c| **c != 0)
-        .map(|c| *c as char)
-        .collect::<String>();
-
-    match ipc::parser::parse_message(&input) {
-        Ok(cmd) => {
-            // send IPC response
-            stream.write(format!("{}", cmd).as_bytes()).ok();
-            // send to main thread
-            if let Err(err) = s.send((input, cmd)) {
-                error!("{}", err);
+/// aliases are in the config, so parse on the main thread
+pub fn parse(s: &util::Sender<Message>, input: &str) -> Result<Vec<Step>, String> {
+    let (reply_s, reply_r) = channel::bounded(1);
+    s.send(Message::Parse(input.to_string(), reply_s)).map_err(|err| err.to_string())?;
+    reply_r.recv().map_err(|err| err.to_string())?
+}
+
+/// run commands on the main thread and wait for the results
+pub fn dispatch(
+    s: &util::Sender<Message>,
+    input: String,
+    steps: Vec<Step>,
+) -> Result<Vec<Outcome>, String> {
+    let (reply_s, reply_r) = channel::bounded(1);
+    s.send(Message::Command(input, steps, reply_s)).map_err(|err| err.to_string())?;
+    reply_r.recv().map_err(|err| err.to_string())
+}
+
+/// a single command replies as normal, sequences reply with a list of results
+pub fn get_response(id: Value, mut outcomes: Vec<Outcome>) -> Response {
+    if outcomes.len() == 1 {
+        return match outcomes.remove(0).result {
+            Some(Ok(result)) => Response::ok(id, result),
+            Some(Err(err)) => Response::err(id, ErrorKind::Command, err),
+            None => Response::ok(id, Value::Null),
+        };
+    }
+    let results = outcomes.into_iter().map(|Outcome { input, result, .. }| {
+        match result {
+            Some(Ok(result)) => json!({ "command": input, "ok": true, "result": result }),
+            Some(Err(err)) => json!({ "command": input, "ok": false, "error": err }),
+            None => json!({ "command": input, "ok": false, "skipped": true }),
+        }
+    }).collect();
+    Response::ok(id, Value::Array(results))
+}
+
+/// subscriptions take over the connection, so have to be sent alone
+pub fn get_subscription(steps: &[Step]) -> Option<Vec<Event>> {
+    if steps.len() == 1 {
+        if let Command::Subscribe(events) = &steps[0].command {
+            return Some(events.clone());
+        }
+    }
+    None
+}
+
+fn stream_text_events(
+    mut stream: UnixStream,
+    s: &util::Sender<Message>,
+    input: String,
+    events: Vec<Event>,
+) {
+    let description = format!("{}", Command::Subscribe(events.clone()));
+    let subscription = match start_subscription(s, input, events) {
+        Ok(subscription) => subscription,
+        Err(err) => {
+            stream.write_all(format!("e:{}", err).as_bytes()).ok();
+            return;
+        },
+    };
+    if writeln!(stream, "{}", description).is_err() {
+        return;
+    }
+    // one event per line
+    forward_events(stream, subscription, |stream, payload| writeln!(stream, "{}", payload));
+}
+
+fn stream_events(
+    mut stream: UnixStream,
+    s: &util::Sender<Message>,
+    id: Value,
+    input: String,
+    events: Vec<Event>,
+) {
+    let subscription = match start_subscription(s, input, events) {
+        Ok(subscription) => subscription,
+        Err(err) => {
+            let response = Response::err(id, ErrorKind::Command, err);
+            protocol::write_response(&mut stream, &response).ok();
+            return;
+        },
+    };
+    if protocol::write_response(&mut stream, &Response::ok(id.clone(), Value::Null)).is_err() {
+        return;
+    }
+    // the rest of the connection is events
+    forward_events(stream, subscription, move |stream, payload| {
+        protocol::write_response(stream, &Response::event(id.clone(), payload))
+    });
+}
+
+/// write events from another thread until either side hangs up
+fn forward_events<F>(mut stream: UnixStream, subscription: Subscription, mut write: F)
+where
+    F: FnMut(&mut UnixStream, Value) -> io::Result<()> + Send + 'static,
+{
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(err) => {
+            error!("IPC connection error: {}", err);
+            return;
+        },
+    };
+    let events_r = subscription.events.clone();
+    thread::spawn(move || {
+        for payload in events_r.iter() {
+            if write(&mut writer, payload).is_err() {
+                break;
             }
         }
-        Err(_err) => {
-            stream
-                .write(format!("e:cannot parse {:?}", input).as_bytes())
-                .ok();
+        // stop the read below if the client stopped reading first
+        writer.shutdown(Shutdown::Both).ok();
+    });
+    // clients don't send anything else, but may shut down writing before
+    // they hang up, so wait for that after reading to the end
+    let mut buf = [0; 64];
+    while let Ok(len) = stream.read(&mut buf) {
+        if len == 0 {
+            util::wait_hangup(stream.as_raw_fd()).ok();
+            break;
         }
     }
 }
+
+/// events forwarded from the main thread, until dropped
+pub struct Subscription {
+    pub events: channel::Receiver<Value>,
+    listeners: Listeners,
+    s: util::Sender<Message>,
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        let listeners = std::mem::take(&mut self.listeners);
+        self.s.send(Message::Unsubscribe(listeners)).ok();
+    }
+}
+
+/// ask the main thread to add listeners that forward events
+pub fn start_subscription(
+    s: &util::Sender<Message>,
+    input: String,
+    events: Vec<Event>,
+) -> Result<Subscription, String> {
+    let (events_s, events_r) = channel::unbounded();
+    let (reply_s, reply_r) = channel::bounded(1);
+    s.send(Message::Subscribe(input, events, events_s, reply_s))
+        .map_err(|err| err.to_string())?;
+    let listeners = reply_r.recv().map_err(|err| err.to_string())?;
+    Ok(Subscription { events: events_r, listeners, s: s.clone() })
+}
+
+/// answers like the main thread would, with `?` as an alias for `get windows`,
+/// failing commands that mention #missing
+#[cfg(test)]
+pub fn fake_main_thread(
+    sender: channel::Sender<util::Sender<Message>>,
+    events: channel::Sender<channel::Sender<Value>>,
+) {
+    let mut aliases = std::collections::HashMap::new();
+    aliases.insert("?".to_string(), "get windows".to_string());
+    let context = glib::MainContext::new();
+    let _owner = context.acquire();
+    let (s, r) = glib::MainContext::channel(glib::PRIORITY_DEFAULT);
+    r.attach(&context, move |message| {
+        match message {
+            Message::Parse(input, reply) => {
+                reply.send(parse_message(&input, &aliases)).ok();
+            },
+            Message::Command(_input, steps, reply) => {
+                let outcomes = steps.into_iter().map(|step| Outcome {
+                    description: step.command.to_string(),
+                    result: Some(if step.input.contains("#missing") {
+                        Err("nothing matches #missing".to_string())
+                    } else {
+                        Ok(json!(step.input))
+                    }),
+                    input: step.input,
+                }).collect();
+                reply.send(outcomes).ok();
+            },
+            Message::Subscribe(_input, _events, sender, reply) => {
+                events.send(sender).ok();
+                reply.send(Vec::new()).ok();
+            },
+            Message::Unsubscribe(_listeners) => {},
+        }
+        glib::Continue(true)
+    });
+    sender.send(s).ok();
+    loop {
+        context.iteration(true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn send_text(s: &util::Sender<Message>, input: &str) -> String {
+        let (mut client, server) = UnixStream::pair().unwrap();
+        thread::spawn(clone!(s move || handle_stream(server, s)));
+        client.write_all(input.as_bytes()).unwrap();
+        client.shutdown(Shutdown::Write).unwrap();
+        let mut reply = String::new();
+        client.read_to_string(&mut reply).unwrap();
+        reply
+    }
+
+    #[test]
+    fn text_messages() {
+        let (sender_s, sender_r) = channel::bounded(1);
+        let (events_s, _events_r) = channel::unbounded();
+        thread::spawn(move || fake_main_thread(sender_s, events_s));
+        let s = sender_r.recv_timeout(Duration::from_secs(5)).unwrap();
+
+        // a single byte, then more than fits in one read
+        assert_eq!(send_text(&s, "?"), "\"get windows\"");
+        let input = format!("set #note {}", "x".repeat(5000));
+        assert_eq!(send_text(&s, &input), json!(input).to_string());
+        assert!(send_text(&s, "shw #bar").starts_with("e:"));
+    }
+}
@@ -1,33 +1,109 @@
 pub mod commands;
+mod dbus;
 mod display;
 mod listen;
 pub mod parser;
 pub mod exec;
+pub mod protocol;
+mod query;
+pub mod selector;
 pub use self::listen::listen;
 
-use std::io::{Error, Read, Write};
+use crate::config::{get_socket_path, CAKEYBAR_SOCKET, SOCKET_DIR};
+use std::fs::read_dir;
+use std::io::{BufRead, BufReader, Error, Write};
+use std::net::Shutdown;
 use std::os::unix::net::UnixStream;
+use std::path::Path;
 
-pub fn send(input: &str) -> Result<String, Error> {
-    let mut conn = UnixStream::connect(crate::config::CAKEYBAR_SOCKET.to_owned())?;
-    conn.write(input.as_bytes())?;
-    let mut data = String::new();
-    conn.read_to_string(&mut data)?;
-    Ok(data)
+/// names of instances with a socket that accepts connections
+pub fn get_instances() -> Vec<String> {
+    // every instance uses the override, so there's only one to find
+    if let Some(path) = &*CAKEYBAR_SOCKET {
+        let path = Path::new(path);
+        if UnixStream::connect(path).is_err() {
+            return Vec::new();
+        }
+        let name = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("default");
+        return vec![name.to_string()];
+    }
+    let entries = match read_dir(&*SOCKET_DIR) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    let mut instances = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension().map_or(false, |ext| ext == "sock")
+                && UnixStream::connect(path).is_ok()
+        })
+        .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+        .collect::<Vec<String>>();
+    instances.sort();
+    instances
+}
+
+pub fn send(instance: &str, input: &str) -> Result<UnixStream, Error> {
+    let mut conn = UnixStream::connect(get_socket_path(instance))?;
+    conn.write_all(input.as_bytes())?;
+    // the message ends when writing does
+    conn.shutdown(Shutdown::Write)?;
+    Ok(conn)
+}
+
+pub fn send_json(instance: &str, input: &str) -> Result<UnixStream, Error> {
+    let mut conn = UnixStream::connect(get_socket_path(instance))?;
+    // replies from different instances can be told apart by id
+    let request = serde_json::json!({
+        "version": protocol::VERSION,
+        "id": instance,
+        "kind": "command",
+        "command": input,
+    });
+    protocol::write_handshake(&mut conn)?;
+    protocol::write_frame(&mut conn, request.to_string().as_bytes())?;
+    conn.shutdown(Shutdown::Write)?;
+    Ok(conn)
 }
 
-pub fn send_message(input: &str) {
-    info!("sending {:?} via IPC...", input);
-    match send(input) {
-        Ok(res) => {
-            if res.starts_with("e:") {
-                error!("{}", &res[2..]);
-            } else if res.starts_with("w:") {
-                warn!("{}", &res[2..]);
-            } else {
-                info!("{}", res);
+pub fn send_message(instance: &str, input: &str) {
+    info!("sending {:?} to {} via IPC...", input, instance);
+    match send(instance, input) {
+        Ok(conn) => {
+            // sequences reply with a line per command, subscriptions a line per event
+            for line in BufReader::new(conn).lines() {
+                let res = match line {
+                    Ok(res) => res,
+                    Err(err) => {
+                        error!("{}", err.to_string().to_lowercase());
+                        break;
+                    },
+                };
+                if res.starts_with("e:") {
+                    error!("{}", &res[2..]);
+                } else if res.starts_with("w:") {
+                    warn!("{}", &res[2..]);
+                } else if res.starts_with('{') || res.starts_with('[') {
+                    // JSON data and events
+                    println!("{}", res);
+                } else {
+                    info!("{}", res);
+                }
             }
         }
         Err(err) => error!("{}", err.to_string().to_lowercase()),
     }
 }
+
+pub fn send_json_message(instance: &str, input: &str) {
+    let result = send_json(instance, input).and_then(|mut conn| {
+        while let Some(frame) = protocol::read_frame(&mut conn)? {
+            println!("{}", String::from_utf8_lossy(&frame));
+        }
+        Ok(())
+    });
+    if let Err(err) = result {
+        error!("{}", err.to_string().to_lowercase());
+    }
+}
@@ -1,17 +1,61 @@
 use nom::types::CompleteStr as Input;
 use nom::*;
 use crate::wm::ipc::commands::*;
+use crate::wm::events::Event;
+use std::collections::HashMap;
+
+named!(simple<Input,Simple>,
+    alt!(
+        do_parse!(char!('#') >> name: pattern >> (Simple::Id(name))) |
+        do_parse!(char!('.') >> name: pattern >> (Simple::Class(name))) |
+        do_parse!(
+            char!('[') >> key: selector_name >>
+            char!('=') >> value: pattern >>
+            char!(']') >>
+            (Simple::Attribute(key, value))
+        )
+    )
+);
+
+named!(compound<Input,Compound>,
+    map!(many1!( simple ), Compound)
+);
+
+// whitespace between compounds matches descendants, `>` direct children
+named!(combinator<Input,Combinator>,
+    alt!(
+        value!(Combinator::Child, tuple!(multispace0, char!('>'), multispace0)) |
+        value!(Combinator::Descendant, multispace1)
+    )
+);
 
 named!(selector<Input,Selector>,
+    do_parse!(
+        first: compound >>
+        rest: many0!( tuple!(combinator, compound) ) >>
+        (Selector(std::iter::once((Combinator::Descendant, first)).chain(rest).collect()))
+    )
+);
+
+// commas between selectors match any of them
+named!(selector_separator<Input,Input>,
+    recognize!(tuple!(multispace0, char!(','), multispace0))
+);
+
+named!(selectors<Input,Selectors>,
     do_parse!(
         multispace1 >>
-        type_: one_of!("#.") >>
-        selector: selector_name  >>
-        (if type_ == '#' {
-            Selector::Id(selector)
-        } else {
-            Selector::Class(selector)
-        })
+        first: selector >>
+        rest: many0!( preceded!(selector_separator, selector) ) >>
+        (Selectors(std::iter::once(first).chain(rest).collect()))
+    )
+);
+
+// a name that can contain wildcards
+named!(pattern<Input,String>,
+    do_parse!(
+        name: many1!( alt!( alphanumeric1 | is_a!("_-*?") )) >>
+        (name.iter().map(|s| s.to_string()).collect::<String>())
     )
 );
 
@@ -60,34 +104,433 @@ named!(reload<Input,Command>,
 named!(focus<Input,Command>,
     do_parse!(
         multispace0 >> tag!("focus") >>
-        selector: selector >>
-        (Command::Focus(selector))
+        selectors: selectors >>
+        (Command::Focus(selectors))
     )
 );
 
 named!(show<Input,Command>,
     do_parse!(
         multispace0 >> tag!("show") >>
-        selectors: many1!( selector ) >>
-        (Command::Show(Selectors(selectors)))
+        selectors: selectors >>
+        (Command::Show(selectors))
     )
 );
 
 named!(hide<Input,Command>,
     do_parse!(
         multispace0 >> tag!("hide") >>
-        selectors: many1!( selector ) >>
-        (Command::Hide(Selectors(selectors)))
+        selectors: selectors >>
+        (Command::Hide(selectors))
+    )
+);
+
+named!(toggle<Input,Command>,
+    do_parse!(
+        multispace0 >> tag!("toggle") >>
+        selectors: selectors >>
+        (Command::Toggle(selectors))
+    )
+);
+
+named!(id<Input,String>,
+    do_parse!(
+        multispace1 >> char!('#') >>
+        name: selector_name >>
+        (name)
+    )
+);
+
+named!(class<Input,String>,
+    do_parse!(
+        multispace1 >> char!('.') >>
+        name: selector_name >>
+        (name)
+    )
+);
+
+// classes end with `--`, so markup can start with a `.`
+named!(set_classes<Input,Vec<String>>,
+    terminated!(many0!( class ), preceded!(multispace1, tag!("--")))
+);
+
+named!(set<Input,Command>,
+    do_parse!(
+        multispace0 >> tag!("set") >>
+        name: id >>
+        classes: opt!( set_classes ) >>
+        markup: get_rest_opt >>
+        (Command::Set(name, classes.unwrap_or_default(), markup))
+    )
+);
+
+named!(tooltip<Input,Command>,
+    do_parse!(
+        multispace0 >> tag!("tooltip") >>
+        name: id >>
+        text: get_rest_opt >>
+        (Command::Tooltip(name, text.unwrap_or_default()))
+    )
+);
+
+named!(workspace<Input,Command>,
+    do_parse!(
+        multispace0 >> tag!("workspace") >>
+        target: map_opt!(get_rest_opt, get_workspace_target) >>
+        (Command::Workspace(target))
+    )
+);
+
+fn get_workspace_target(rest: Option<String>) -> Option<WorkspaceTarget> {
+    let rest = rest?;
+    let mut words = rest.split_whitespace();
+    match words.next()? {
+        "next" => Some(WorkspaceTarget::Next(words.next().map(str::to_string))),
+        "prev" => Some(WorkspaceTarget::Prev(words.next().map(str::to_string))),
+        _ => Some(WorkspaceTarget::Name(rest.trim().to_string())),
+    }
+}
+
+named!(exec<Input,Command>,
+    do_parse!(
+        multispace0 >> tag!("exec") >>
+        command: map_opt!(get_rest_opt, |rest| rest) >>
+        (Command::Exec(command))
+    )
+);
+
+named!(watch<Input,Command>,
+    do_parse!(
+        multispace0 >> tag!("watch") >>
+        multispace1 >>
+        command: alt!(
+            value!(Command::Watch(true), tag!("on")) |
+            value!(Command::Watch(false), tag!("off")) |
+            value!(Command::WatchStatus, tag!("status"))
+        ) >>
+        (command)
+    )
+);
+
+named!(refresh<Input,Command>,
+    do_parse!(
+        multispace0 >> tag!("refresh") >>
+        selectors: selectors >>
+        (Command::Refresh(selectors))
+    )
+);
+
+named!(class_command<Input,Command>,
+    do_parse!(
+        multispace0 >>
+        command: alt!( tag!("addclass") | tag!("removeclass") | tag!("toggleclass") ) >>
+        selectors: selectors >>
+        multispace1 >>
+        class: selector_name >>
+        (match command.0 {
+            "addclass" => Command::AddClass(selectors, class),
+            "removeclass" => Command::RemoveClass(selectors, class),
+            _ => Command::ToggleClass(selectors, class),
+        })
+    )
+);
+
+named!(event<Input,Event>,
+    do_parse!(
+        multispace1 >>
+        event: alt!(
+            value!(Event::Workspace, tag!("workspace")) |
+            value!(Event::WindowTitle, tag!("title")) |
+            value!(Event::Mode, tag!("mode")) |
+            value!(Event::Windows, tag!("windows"))
+        ) >>
+        (event)
+    )
+);
+
+named!(subscribe<Input,Command>,
+    do_parse!(
+        multispace0 >> tag!("subscribe") >>
+        events: many1!( event ) >>
+        (Command::Subscribe(events))
+    )
+);
+
+named!(query<Input,Query>,
+    alt!(
+        do_parse!(
+            multispace1 >>
+            query: alt!(
+                value!(Query::Windows, tag!("windows")) |
+                value!(Query::Components, tag!("components")) |
+                value!(Query::Config, tag!("config")) |
+                value!(Query::Workspaces, tag!("workspaces"))
+            ) >>
+            (query)
+        ) |
+        map!(selectors, Query::Component)
+    )
+);
+
+named!(get<Input,Command>,
+    do_parse!(
+        multispace0 >> tag!("get") >>
+        query: query >>
+        (Command::Get(query))
     )
 );
 
 named!(get_command<Input,Command>,
-    alt!( show | hide | focus | reload )
+    alt!( show | hide | toggle | refresh | class_command | focus | set | tooltip | workspace | exec | watch | reload | subscribe | get )
 );
 
-pub fn parse_message(input: &str) -> Result<Command, String> {
+pub fn parse_command(input: &str) -> Result<Command, String> {
     match get_command(Input(input)) {
-        Ok((_remainder, command)) => Ok(command),
-        Err(err) => Err(format!("{:?}", err)),
+        Ok((remainder, command)) => {
+            if remainder.trim().is_empty() {
+                Ok(command)
+            } else {
+                Err(format!("unexpected {:?} in {:?}", remainder.trim(), input.trim()))
+            }
+        },
+        Err(_err) => Err(format!("cannot parse {:?}", input.trim())),
+    }
+}
+
+/// split a message on `;` and `&&` and parse each command, expanding aliases
+pub fn parse_message(input: &str, aliases: &HashMap<String, String>) -> Result<Vec<Step>, String> {
+    parse_steps(input, aliases, &mut Vec::new())
+}
+
+fn parse_steps(
+    input: &str,
+    aliases: &HashMap<String, String>,
+    expanding: &mut Vec<String>,
+) -> Result<Vec<Step>, String> {
+    let mut steps = Vec::new();
+    for (condition, part) in split_message(input)? {
+        let part = part.trim();
+        if part.is_empty() {
+            if condition == Condition::Success {
+                return Err("missing command after &&".to_string());
+            }
+            continue;
+        }
+        if let Some(alias) = aliases.get(part) {
+            if expanding.iter().any(|name| name == part) {
+                return Err(format!("alias {} refers to itself", part));
+            }
+            expanding.push(part.to_string());
+            let mut expanded = parse_steps(alias, aliases, expanding)?;
+            expanding.pop();
+            // the alias runs under the condition it was used with
+            expanded[0].condition = condition;
+            steps.extend(expanded);
+            continue;
+        }
+        steps.push(Step {
+            input: part.to_string(),
+            command: parse_command(part)?,
+            condition,
+        });
+    }
+    if steps.is_empty() {
+        Err("no command given".to_string())
+    } else {
+        Ok(steps)
+    }
+}
+
+/// commands whose markup or shell command runs to the end of the message
+const PAYLOAD_COMMANDS: &[&str] = &["set", "tooltip", "exec"];
+
+fn split_message(input: &str) -> Result<Vec<(Condition, &str)>, String> {
+    let mut parts = Vec::new();
+    let mut condition = Condition::Always;
+    let mut start = 0;
+    while !takes_payload(&input[start..]) {
+        let part = &input[start..];
+        let (end, separator) = match find_separator(part) {
+            Some(separator) => separator,
+            None => break,
+        };
+        if separator == "&&" && part[..end].trim().is_empty() {
+            return Err("missing command before &&".to_string());
+        }
+        parts.push((condition, &part[..end]));
+        condition = if separator == "&&" { Condition::Success } else { Condition::Always };
+        start += end + separator.len();
+    }
+    parts.push((condition, &input[start..]));
+    Ok(parts)
+}
+
+fn takes_payload(part: &str) -> bool {
+    part.split_whitespace().next().map_or(false, |name| PAYLOAD_COMMANDS.contains(&name))
+}
+
+fn find_separator(part: &str) -> Option<(usize, &'static str)> {
+    part.char_indices().find_map(|(index, c)| match c {
+        ';' => Some((index, ";")),
+        '&' if part[index..].starts_with("&&") => Some((index, "&&")),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Result<Vec<(Condition, String)>, String> {
+        let steps = parse_message(input, &HashMap::new())?;
+        Ok(steps.into_iter().map(|step| (step.condition, step.input)).collect())
+    }
+
+    fn step(condition: Condition, input: &str) -> (Condition, String) {
+        (condition, input.to_string())
+    }
+
+    #[test]
+    fn sequences() {
+        assert_eq!(parse("show #a; hide #b && focus #c"), Ok(vec![
+            step(Condition::Always, "show #a"),
+            step(Condition::Always, "hide #b"),
+            step(Condition::Success, "focus #c"),
+        ]));
+        assert_eq!(parse("hide #a&&show #b"), Ok(vec![
+            step(Condition::Always, "hide #a"),
+            step(Condition::Success, "show #b"),
+        ]));
+    }
+
+    #[test]
+    fn empty_parts() {
+        assert_eq!(parse(";; show #a ;"), Ok(vec![step(Condition::Always, "show #a")]));
+        assert_eq!(parse(" ; "), Err("no command given".to_string()));
+        assert_eq!(parse("&& show #a"), Err("missing command before &&".to_string()));
+        assert_eq!(parse("show #a; && hide #b"), Err("missing command before &&".to_string()));
+        assert_eq!(parse("show #a &&"), Err("missing command after &&".to_string()));
+    }
+
+    #[test]
+    fn payloads_take_the_rest() {
+        assert_eq!(parse("show #a; exec a; b && c"), Ok(vec![
+            step(Condition::Always, "show #a"),
+            step(Condition::Always, "exec a; b && c"),
+        ]));
+        assert_eq!(parse("set #x Tom &amp; Jerry; more"), Ok(vec![
+            step(Condition::Always, "set #x Tom &amp; Jerry; more"),
+        ]));
+        assert_eq!(parse("hide #a && tooltip #x a && b"), Ok(vec![
+            step(Condition::Always, "hide #a"),
+            step(Condition::Success, "tooltip #x a && b"),
+        ]));
+        // only as the command name
+        assert_eq!(parse("show #exec; hide #set"), Ok(vec![
+            step(Condition::Always, "show #exec"),
+            step(Condition::Always, "hide #set"),
+        ]));
+    }
+
+    fn get_set(input: &str) -> (String, Vec<String>, Option<String>) {
+        match parse_command(input) {
+            Ok(Command::Set(name, classes, markup)) => (name, classes, markup),
+            _ => panic!("{:?} isn't a set command", input),
+        }
+    }
+
+    #[test]
+    fn set_classes() {
+        let classes = |classes: &[&str]| classes.iter().map(|c| c.to_string()).collect();
+        assert_eq!(
+            get_set("set #ci .failed .urgent -- <b>failed</b>"),
+            ("ci".to_string(), classes(&["failed", "urgent"]), Some("<b>failed</b>".to_string())),
+        );
+        assert_eq!(
+            get_set("set #disk .5 GB free"),
+            ("disk".to_string(), vec![], Some(".5 GB free".to_string())),
+        );
+        assert_eq!(
+            get_set("set #x .note text"),
+            ("x".to_string(), vec![], Some(".note text".to_string())),
+        );
+        assert_eq!(get_set("set #x .note --"), ("x".to_string(), classes(&["note"]), None));
+        assert_eq!(
+            get_set("set #x -- -- dashes"),
+            ("x".to_string(), vec![], Some("-- dashes".to_string())),
+        );
+        assert_eq!(get_set("set #x"), ("x".to_string(), vec![], None));
+    }
+
+    fn get_selectors(input: &str) -> String {
+        match parse_command(&format!("show {}", input)) {
+            Ok(Command::Show(selectors)) => selectors.to_string(),
+            Ok(_) => unreachable!(),
+            Err(err) => err,
+        }
+    }
+
+    #[test]
+    fn selector_lists() {
+        // commas separate alternatives
+        assert_eq!(get_selectors(".stats, #bar"), ".stats, #bar");
+        assert_eq!(get_selectors("#a,#b ,  .c"), "#a, #b, .c");
+        assert_eq!(
+            get_selectors("#bar.focused[monitor=1], #float-*"),
+            "#bar.focused[monitor=1], #float-*",
+        );
+        // whitespace for descendants, > for children
+        assert_eq!(get_selectors("#left   .stat"), "#left .stat");
+        assert_eq!(get_selectors("#left>.stat"), "#left > .stat");
+        assert_eq!(
+            get_selectors("#left .stat, #right > .box>#cpu"),
+            "#left .stat, #right > .box > #cpu",
+        );
+        let selectors = match parse_command("hide #left .stat, [type=tray]") {
+            Ok(Command::Hide(selectors)) => selectors,
+            _ => panic!("can't parse hide"),
+        };
+        assert_eq!(selectors.0.len(), 2);
+        let combinators = selectors.0[0].0.iter().map(|(combinator, _)| *combinator);
+        assert!(combinators.eq(vec![Combinator::Descendant, Combinator::Descendant]));
+        assert!(get_selectors("#left >").starts_with("unexpected"));
+        assert!(get_selectors("#left ,").starts_with("unexpected"));
+    }
+
+    #[test]
+    fn class_commands() {
+        match parse_command("addclass #bar.x, #left .stat presentation") {
+            Ok(Command::AddClass(selectors, class)) => {
+                assert_eq!(selectors.to_string(), "#bar.x, #left .stat");
+                assert_eq!(class, "presentation");
+            },
+            _ => panic!("can't parse addclass"),
+        }
+    }
+
+    #[test]
+    fn watch_commands() {
+        let get_watch = |input| match parse_command(input) {
+            Ok(Command::Watch(enabled)) => Some(enabled.to_string()),
+            Ok(Command::WatchStatus) => Some("status".to_string()),
+            _ => None,
+        };
+        assert_eq!(get_watch("watch on"), Some("true".to_string()));
+        assert_eq!(get_watch(" watch  off "), Some("false".to_string()));
+        assert_eq!(get_watch("watch status"), Some("status".to_string()));
+        assert_eq!(get_watch("watch"), None);
+        assert_eq!(get_watch("watch maybe"), None);
+        assert_eq!(get_watch("watch onward"), None);
+    }
+
+    #[test]
+    fn trailing_input() {
+        assert!(parse_command("hide #bar").is_ok());
+        assert!(parse_command("hide #bar ").is_ok());
+        assert_eq!(
+            parse_command("hide #bar garbage").err(),
+            Some("unexpected \"garbage\" in \"hide #bar garbage\"".to_string()),
+        );
+        assert!(parse_command("watch on please").is_err());
     }
 }
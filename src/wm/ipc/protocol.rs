@@ -0,0 +1,226 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use std::io::{self, Read, Write};
+
+// a JSON connection starts with a zero byte, which text commands never do,
+// followed by messages framed with a big-endian u32 length
+
+pub const VERSION: u32 = 1;
+/// frames are only ever small JSON objects
+pub const MAX_FRAME_LENGTH: usize = 1024 * 1024;
+
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    #[serde(default = "default_version")]
+    pub version: u32,
+    #[serde(default)]
+    pub id: Value,
+    #[serde(flatten)]
+    pub kind: RequestKind,
+}
+
+fn default_version() -> u32 {
+    VERSION
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum RequestKind {
+    /// a command in the text syntax, eg `show #bar`
+    Command { command: String },
+}
+
+#[derive(Debug, Serialize)]
+pub struct Response {
+    pub version: u32,
+    pub id: Value,
+    pub ok: bool,
+    #[serde(flatten)]
+    pub body: ResponseBody,
+}
+
+#[derive(Debug, Serialize)]
+pub enum ResponseBody {
+    #[serde(rename = "result")]
+    Ok(Value),
+    #[serde(rename = "error")]
+    Err(ResponseError),
+    /// sent for each event after a subscription
+    #[serde(rename = "event")]
+    Event(Value),
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResponseError {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorKind {
+    /// the frame isn't a valid request
+    InvalidRequest,
+    /// the request version is newer than ours
+    UnsupportedVersion,
+    /// the command doesn't parse
+    Parse,
+    /// the command failed to run
+    Command,
+}
+
+impl Response {
+    pub fn ok(id: Value, result: Value) -> Self {
+        Response { version: VERSION, id, ok: true, body: ResponseBody::Ok(result) }
+    }
+    pub fn event(id: Value, event: Value) -> Self {
+        Response { version: VERSION, id, ok: true, body: ResponseBody::Event(event) }
+    }
+    pub fn err(id: Value, kind: ErrorKind, message: String) -> Self {
+        Response {
+            version: VERSION,
+            id,
+            ok: false,
+            body: ResponseBody::Err(ResponseError { kind, message }),
+        }
+    }
+}
+
+/// parse a frame into a request, or the error response to send back
+pub fn parse_request(frame: &[u8]) -> Result<Request, Response> {
+    let request: Request = serde_json::from_slice(frame).map_err(|err| {
+        // try to keep the id so the client can match the error up
+        let id = serde_json::from_slice::<Value>(frame).ok()
+            .and_then(|value| value.get("id").cloned())
+            .unwrap_or(Value::Null);
+        Response::err(id, ErrorKind::InvalidRequest, err.to_string())
+    })?;
+    if request.version > VERSION {
+        return Err(Response::err(
+            request.id,
+            ErrorKind::UnsupportedVersion,
+            format!("version {} is not supported (max {})", request.version, VERSION),
+        ));
+    }
+    Ok(request)
+}
+
+pub fn is_framed(first_byte: u8) -> bool {
+    first_byte == 0
+}
+
+/// start a JSON connection
+pub fn write_handshake<W: Write>(writer: &mut W) -> io::Result<()> {
+    writer.write_all(&[0])
+}
+
+/// read one frame, returning None at the end of the stream
+pub fn read_frame<R: Read>(reader: &mut R) -> io::Result<Option<Vec<u8>>> {
+    let mut header = [0; 4];
+    let mut read = 0;
+    while read < header.len() {
+        match reader.read(&mut header[read..]) {
+            Ok(0) if read == 0 => return Ok(None),
+            Ok(0) => return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "stream ended inside a frame header",
+            )),
+            Ok(length) => read += length,
+            Err(ref err) if err.kind() == io::ErrorKind::Interrupted => {},
+            Err(err) => return Err(err),
+        }
+    }
+    let length = u32::from_be_bytes(header) as usize;
+    if length > MAX_FRAME_LENGTH {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("frame length {} is over the limit of {}", length, MAX_FRAME_LENGTH),
+        ));
+    }
+    let mut frame = vec![0; length];
+    reader.read_exact(&mut frame)?;
+    Ok(Some(frame))
+}
+
+pub fn write_frame<W: Write>(writer: &mut W, data: &[u8]) -> io::Result<()> {
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(data)?;
+    writer.flush()
+}
+
+pub fn write_response<W: Write>(writer: &mut W, response: &Response) -> io::Result<()> {
+    let data = serde_json::to_vec(response)
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+    write_frame(writer, &data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::Cursor;
+
+    fn get_error(frame: &str) -> Value {
+        let response = parse_request(frame.as_bytes()).unwrap_err();
+        serde_json::to_value(&response).unwrap()
+    }
+
+    #[test]
+    fn requests() {
+        let request = parse_request(br#"{"id": 3, "kind": "command", "command": "show #bar"}"#)
+            .unwrap();
+        assert_eq!(request.version, VERSION);
+        assert_eq!(request.id, json!(3));
+        let RequestKind::Command { command } = request.kind;
+        assert_eq!(command, "show #bar");
+    }
+
+    #[test]
+    fn invalid_requests() {
+        let response = get_error(r#"{"id": "a", "kind": "nothing"}"#);
+        assert_eq!(response["id"], json!("a"));
+        assert_eq!(response["ok"], json!(false));
+        assert_eq!(response["error"]["kind"], json!("invalid-request"));
+
+        let response = get_error("{");
+        assert_eq!(response["id"], Value::Null);
+        assert_eq!(response["error"]["kind"], json!("invalid-request"));
+
+        let response = get_error(r#"{"version": 99, "id": 1, "kind": "command", "command": ""}"#);
+        assert_eq!(response["id"], json!(1));
+        assert_eq!(response["error"]["kind"], json!("unsupported-version"));
+    }
+
+    #[test]
+    fn frame_round_trip() {
+        let mut buffer = Vec::new();
+        write_frame(&mut buffer, b"first").unwrap();
+        write_frame(&mut buffer, b"").unwrap();
+        write_response(&mut buffer, &Response::ok(json!(1), json!("done"))).unwrap();
+
+        let mut reader = Cursor::new(buffer);
+        assert_eq!(read_frame(&mut reader).unwrap(), Some(b"first".to_vec()));
+        assert_eq!(read_frame(&mut reader).unwrap(), Some(Vec::new()));
+        let response = read_frame(&mut reader).unwrap().unwrap();
+        let response = serde_json::from_slice::<Value>(&response).unwrap();
+        assert_eq!(response, json!({"version": VERSION, "id": 1, "ok": true, "result": "done"}));
+        assert_eq!(read_frame(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn broken_frames() {
+        let mut reader = Cursor::new(vec![0, 0]);
+        let err = read_frame(&mut reader).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        let mut reader = Cursor::new(vec![0, 0, 0, 5, b'a']);
+        let err = read_frame(&mut reader).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        let length = (MAX_FRAME_LENGTH as u32 + 1).to_be_bytes();
+        let mut reader = Cursor::new(length.to_vec());
+        let err = read_frame(&mut reader).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
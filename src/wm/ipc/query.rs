@@ -0,0 +1,55 @@
+use gtk::prelude::*;
+use serde_json::{json, Value};
+
+use crate::wm;
+use crate::wm::ipc::commands::*;
+use crate::wm::ipc::exec::CommandResult;
+use crate::wm::WMUtil;
+
+pub fn run_query(wm_util: &WMUtil, query: Query) -> CommandResult {
+    match query {
+        Query::Windows => Ok(Value::Array(wm_util.map_windows(|window| {
+            json!({
+                "name": window.get_config().name,
+                "type": window.get_type(),
+                "monitor": window.get_monitor_index(),
+                "visible": window.is_visible(),
+            })
+        }))),
+        Query::Components => Ok(Value::Array(get_components(wm_util, None))),
+        Query::Config => {
+            serde_json::to_value(wm_util.get_resolved_config()).map_err(|err| err.to_string())
+        },
+        Query::Workspaces => match wm_util.get_workspaces() {
+            Some(workspaces) => serde_json::to_value(workspaces).map_err(|err| err.to_string()),
+            None => Err("cannot get workspaces from the window manager".to_string()),
+        },
+        Query::Component(selectors) => {
+            let components = get_components(wm_util, Some(&selectors));
+            if components.is_empty() {
+                Err(format!("no components match {}", selectors))
+            } else {
+                Ok(Value::Array(components))
+            }
+        },
+    }
+}
+
+/// loaded components with what they're currently displaying
+fn get_components(wm_util: &WMUtil, selectors: Option<&Selectors>) -> Vec<Value> {
+    wm_util.map_components(|window, name, widget, path| {
+        if !selectors.map_or(true, |selectors| selectors.matches(path)) {
+            return None;
+        }
+        let type_ = wm_util.get_component_config(&name)
+            .and_then(|config| config.get_string("type"));
+        Some(json!({
+            "name": name,
+            "type": type_,
+            "window": window.get_config().name,
+            "visible": widget.is_visible(),
+            "classes": wm::gtk::get_style_classes(&widget),
+            "text": wm::gtk::get_widget_text(&widget),
+        }))
+    })
+}
@@ -0,0 +1,228 @@
+use crate::wm::ipc::commands::*;
+use glob::Pattern;
+
+/// what selectors can see of a window or component
+#[derive(Clone)]
+pub struct Element {
+    pub name: String,
+    pub classes: Vec<String>,
+    pub attributes: Vec<(&'static str, String)>,
+}
+
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    Pattern::new(pattern).map_or(false, |pattern| pattern.matches(text))
+}
+
+impl Selectors {
+    /// the path is an element and its ancestors, outermost first
+    pub fn matches(&self, path: &[Element]) -> bool {
+        self.0.iter().any(|selector| selector.matches(path))
+    }
+}
+
+impl Selector {
+    pub fn matches(&self, path: &[Element]) -> bool {
+        match (self.0.split_last(), path.split_last()) {
+            (Some(((combinator, compound), compounds)), Some((element, ancestors))) => {
+                compound.matches(element) && matches_ancestors(compounds, *combinator, ancestors)
+            },
+            _ => false,
+        }
+    }
+}
+
+/// the last compound has to match an ancestor, the rest ones further out. a
+/// child combinator means it has to be the closest one
+fn matches_ancestors(
+    compounds: &[(Combinator, Compound)],
+    combinator: Combinator,
+    ancestors: &[Element],
+) -> bool {
+    let ((next_combinator, compound), compounds) = match compounds.split_last() {
+        Some(last) => last,
+        None => return true,
+    };
+    let start = match combinator {
+        Combinator::Descendant => 0,
+        Combinator::Child => ancestors.len().saturating_sub(1),
+    };
+    (start..ancestors.len()).rev().any(|index| {
+        compound.matches(&ancestors[index])
+            && matches_ancestors(compounds, *next_combinator, &ancestors[..index])
+    })
+}
+
+impl Compound {
+    pub fn matches(&self, element: &Element) -> bool {
+        self.0.iter().all(|simple| match simple {
+            Simple::Id(name) => glob_matches(name, &element.name),
+            Simple::Class(class) => element.classes
+                .iter()
+                .any(|element_class| glob_matches(class, element_class)),
+            Simple::Attribute(key, value) => element.attributes
+                .iter()
+                .any(|(element_key, element_value)| {
+                    element_key == key && glob_matches(value, element_value)
+                }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn element(name: &str, classes: &[&str], type_: &str) -> Element {
+        Element {
+            name: name.to_string(),
+            classes: classes.iter().map(|class| class.to_string()).collect(),
+            attributes: vec![("type", type_.to_string()), ("monitor", "1".to_string())],
+        }
+    }
+
+    fn id(name: &str) -> Simple {
+        Simple::Id(name.to_string())
+    }
+
+    fn class(name: &str) -> Simple {
+        Simple::Class(name.to_string())
+    }
+
+    fn attribute(key: &str, value: &str) -> Simple {
+        Simple::Attribute(key.to_string(), value.to_string())
+    }
+
+    fn path() -> Vec<Element> {
+        vec![
+            element("bar", &["focused"], "bar"),
+            element("left", &[], "container"),
+            element("stats", &["box"], "container"),
+            element("cpu", &["stat", "high"], "cpu"),
+        ]
+    }
+
+    #[test]
+    fn compounds() {
+        let cpu = &path()[3];
+        assert!(Compound(vec![id("cpu"), class("stat")]).matches(cpu));
+        assert!(!Compound(vec![id("cpu"), class("low")]).matches(cpu));
+        assert!(Compound(vec![attribute("type", "cpu"), attribute("monitor", "1")]).matches(cpu));
+        assert!(!Compound(vec![attribute("monitor", "0")]).matches(cpu));
+    }
+
+    #[test]
+    fn globs() {
+        let cpu = &path()[3];
+        assert!(Compound(vec![id("c*")]).matches(cpu));
+        assert!(Compound(vec![id("cp?")]).matches(cpu));
+        assert!(Compound(vec![class("hi*")]).matches(cpu));
+        assert!(!Compound(vec![id("float-*")]).matches(cpu));
+    }
+
+    fn selector(compounds: Vec<(Combinator, Vec<Simple>)>) -> Selector {
+        Selector(compounds
+            .into_iter()
+            .map(|(combinator, simples)| (combinator, Compound(simples)))
+            .collect())
+    }
+
+    fn parse(input: &str) -> Selectors {
+        match crate::wm::ipc::parser::parse_command(&format!("show {}", input)) {
+            Ok(Command::Show(selectors)) => selectors,
+            _ => panic!("can't parse {}", input),
+        }
+    }
+
+    #[test]
+    fn descendants() {
+        use self::Combinator::Descendant as D;
+        // ancestors don't have to be direct parents
+        assert!(selector(vec![(D, vec![id("left")]), (D, vec![class("stat")])]).matches(&path()));
+        assert!(selector(vec![
+            (D, vec![id("bar"), class("focused")]),
+            (D, vec![class("box")]),
+            (D, vec![id("cpu")]),
+        ]).matches(&path()));
+        // the order matters
+        assert!(!selector(vec![(D, vec![id("stats")]), (D, vec![id("left")])]).matches(&path()));
+        assert!(!selector(vec![
+            (D, vec![class("box")]),
+            (D, vec![id("left")]),
+            (D, vec![id("cpu")]),
+        ]).matches(&path()));
+        // the last compound is the element itself
+        assert!(!selector(vec![(D, vec![id("left")])]).matches(&path()));
+    }
+
+    #[test]
+    fn children() {
+        use self::Combinator::{Child as C, Descendant as D};
+        assert!(selector(vec![(D, vec![id("stats")]), (C, vec![class("stat")])]).matches(&path()));
+        // #left is a grandparent
+        assert!(!selector(vec![(D, vec![id("left")]), (C, vec![class("stat")])]).matches(&path()));
+        assert!(selector(vec![
+            (D, vec![id("left")]),
+            (C, vec![id("stats")]),
+            (C, vec![id("cpu")]),
+        ]).matches(&path()));
+        assert!(selector(vec![
+            (D, vec![id("bar")]),
+            (D, vec![id("stats")]),
+            (C, vec![id("cpu")]),
+        ]).matches(&path()));
+        assert!(!selector(vec![
+            (D, vec![id("bar")]),
+            (C, vec![id("stats")]),
+            (C, vec![id("cpu")]),
+        ]).matches(&path()));
+    }
+
+    #[test]
+    fn parsed_selectors() {
+        let cpu = path();
+        let left = &cpu[..2];
+        // `#left .stat` is .stat inside #left, not #left itself
+        assert!(parse("#left .stat").matches(&cpu));
+        assert!(!parse("#left .stat").matches(left));
+        assert!(!parse("#left > .stat").matches(&cpu));
+        assert!(parse("#stats > .stat").matches(&cpu));
+        assert!(parse("#left .stat, #left").matches(left));
+        assert!(!parse("#right .stat").matches(&cpu));
+    }
+
+    #[test]
+    fn monitor_instances() {
+        // windows with monitor = "all" have a copy of each component per monitor
+        let copies = (0..2).map(|monitor| vec![
+            Element {
+                name: "bar".to_string(),
+                classes: vec![],
+                attributes: vec![("type", "bar".to_string()), ("monitor", monitor.to_string())],
+            },
+            Element {
+                name: "weather".to_string(),
+                classes: vec![],
+                attributes: vec![("type", "script".to_string()), ("monitor", monitor.to_string())],
+            },
+        ]).collect::<Vec<_>>();
+        let matching = |input| copies.iter()
+            .filter(|path| parse(input).matches(path))
+            .count();
+        assert_eq!(matching("#weather"), 2);
+        assert_eq!(matching("#weather[monitor=1]"), 1);
+        assert_eq!(matching("#bar[monitor=1] #weather"), 1);
+        assert_eq!(matching("[monitor=2] #weather"), 0);
+        assert!(parse("#bar[monitor=0]").matches(&copies[0][..1]));
+        assert!(!parse("#bar[monitor=0]").matches(&copies[1][..1]));
+    }
+
+    #[test]
+    fn alternatives() {
+        let selectors = Selectors(vec![
+            selector(vec![(Combinator::Descendant, vec![id("clock")])]),
+            selector(vec![(Combinator::Descendant, vec![attribute("type", "cpu")])]),
+        ]);
+        assert!(selectors.matches(&path()));
+        assert!(!selectors.matches(&path()[..3]));
+    }
+}
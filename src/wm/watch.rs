@@ -1,102 +1,172 @@
 use inotify::{Inotify, WatchMask};
-use crossbeam_channel as channel;
-use std::{thread, time};
-use crate::util::Timer;
+use std::io;
+use std::os::unix::io::AsRawFd;
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::util::{self, Listener, Wakeup};
 use crate::wm::WMUtil;
 use crate::config::Config;
 use gtk;
 
+/// editors can write several times when saving
+const DEBOUNCE_MS: u64 = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 enum WriteType {
     Config,
     Theme,
 }
 
+/// reloading the config loads the theme too, so one reload covers both
+fn merge_writes(pending: Option<WriteType>, write: WriteType) -> WriteType {
+    match (pending, write) {
+        (Some(WriteType::Config), _) | (_, WriteType::Config) => WriteType::Config,
+        _ => WriteType::Theme,
+    }
+}
+
 pub struct Watcher {
-    timer: Timer,
-    sender: channel::Sender<()>,
+    listener: Listener,
+    wakeup: Wakeup,
+    files: Vec<String>,
 }
 
 impl Watcher {
-    pub fn new(wm_util: &WMUtil, config: &Config) -> Watcher {
-        let configfile = config.get_filename();
+    pub fn new(wm_util: &WMUtil, config: &Config) -> Result<Watcher, String> {
+        let mut configfiles = vec![config.get_filename()];
+        configfiles.extend(config.includes.iter().cloned());
         let theme = config.get_theme();
+        let mut files = configfiles.clone();
+        files.push(theme.clone());
 
-        let (s, r) = channel::unbounded();
-        let (s_dead, r_dead) = channel::unbounded();
+        let (s, r) = util::channel();
+        let (wakeup, woken) = util::wakeup()
+            .map_err(|err| format!("cannot watch files: {}", err))?;
 
         thread::spawn(move || {
-            let mut inotify = Inotify::init().unwrap();
-
-            let file_wd = match inotify.add_watch(&configfile, WatchMask::CLOSE_WRITE) {
-                Ok(watcher) => Some((&configfile, watcher)),
-                Err(err) => { error!("failed to watch {}: {}", &configfile, err); None },
+            let mut inotify = match Inotify::init() {
+                Ok(inotify) => inotify,
+                Err(err) => {
+                    error!("cannot watch files: {}", err);
+                    return;
+                },
             };
+
+            let file_wds = configfiles.iter()
+                .filter_map(|file| {
+                    match inotify.add_watch(file, WatchMask::CLOSE_WRITE) {
+                        Ok(watcher) => Some((file, watcher)),
+                        Err(err) => { error!("failed to watch {}: {}", file, err); None },
+                    }
+                })
+                .collect::<Vec<_>>();
             let theme_wd = match inotify.add_watch(&theme, WatchMask::CLOSE_WRITE) {
                 Ok(watcher) => Some((&theme, watcher)),
                 Err(err) => { error!("failed to watch {}: {}", &theme, err); None },
             };
 
+            let fds = [inotify.as_raw_fd(), woken.as_raw_fd()];
+            let debounce = Duration::from_millis(DEBOUNCE_MS);
             let mut buffer = [0; 1024];
+            let mut pending = None;
+            let mut last_write = Instant::now();
             loop {
-                let events = inotify.read_events(&mut buffer)
-                    .expect("error reading events");
-                for event in events {
-                    if let &Some((_, ref wd)) = &file_wd {
-                        if wd == &event.wd {
-                            if let Err(err) = s.send(WriteType::Config) {
-                                error!("{}", err);
-                            } else {
-                                info!("updated {}", &configfile);
-                            }
+                // sleep until there's a write, or until the last one settles
+                let timeout = pending.map(|_| {
+                    debounce.checked_sub(last_write.elapsed()).unwrap_or_default()
+                });
+                let readable = match util::poll_readable(&fds, timeout) {
+                    Ok(readable) => readable,
+                    Err(err) => {
+                        error!("failed to watch files: {}", err);
+                        break;
+                    },
+                };
+                // unwatched
+                if readable[1] {
+                    break;
+                }
+                if readable[0] {
+                    let events = match inotify.read_events(&mut buffer) {
+                        Ok(events) => events,
+                        Err(ref err) if err.kind() == io::ErrorKind::WouldBlock
+                            || err.kind() == io::ErrorKind::Interrupted => continue,
+                        Err(err) => {
+                            warn!("stopped watching files: {}", err);
+                            break;
+                        },
+                    };
+                    for event in events {
+                        let file_opt = file_wds.iter().find(|(_, wd)| wd == &event.wd);
+                        let theme_opt = theme_wd.iter().find(|(_, wd)| wd == &event.wd);
+                        let (file, wtype) = if let Some((file, _)) = file_opt {
+                            (file, WriteType::Config)
+                        } else if let Some((file, _)) = theme_opt {
+                            (file, WriteType::Theme)
                         } else {
-                            if let Err(err) = s.send(WriteType::Theme) {
-                                error!("{}", err);
-                            } else {
-                                info!("updated {}", &theme);
-                            }
-                        }
-                    } else if let Some(_) = &theme_wd {
-                        // doesn't work when there's no valid config file
-                        // ...which should never happen
-                        error!("this should never happen");
+                            continue;
+                        };
+                        info!("updated {}", file);
+                        pending = Some(merge_writes(pending, wtype));
+                        last_write = Instant::now();
                     }
                 }
-                if r_dead.try_recv().is_ok() {
-                    // remove watchers
-                    if let Some((_, wd)) = file_wd {
-                        inotify.rm_watch(wd).ok();
-                    }
-                    if let Some((_, wd)) = theme_wd {
-                        inotify.rm_watch(wd).ok();
+                if last_write.elapsed() >= debounce {
+                    if let Some(wtype) = pending.take() {
+                        if let Err(err) = s.send(wtype) {
+                            error!("{}", err);
+                        }
                     }
-                    // end thread
-                    break;
-                } else {
-                    thread::sleep(time::Duration::from_millis(50));
                 }
             }
+            // remove watchers
+            for (_, wd) in file_wds {
+                inotify.rm_watch(wd).ok();
+            }
+            if let Some((_, wd)) = theme_wd {
+                inotify.rm_watch(wd).ok();
+            }
         });
 
-        let timer = Timer::add_ms(50, clone!(wm_util move || {
-            if let Ok(wtype) = r.try_recv() {
-                match wtype {
-                    WriteType::Config => {
-                        wm_util.reload_config(None);
-                    },
-                    WriteType::Theme => {
-                        wm_util.load_theme(None);
-                    },
-                }
+        let listener = r.attach(clone!(wm_util move |wtype| {
+            match wtype {
+                WriteType::Config => {
+                    wm_util.reload_config(None).ok();
+                },
+                WriteType::Theme => {
+                    wm_util.load_theme(None).ok();
+                },
             }
             gtk::Continue(true)
         }));
 
-        Watcher { timer, sender: s_dead }
+        Ok(Watcher { listener, wakeup, files })
+    }
+
+    pub fn get_files(&self) -> &[String] {
+        &self.files
     }
+
     pub fn unwatch(&self) {
-        self.timer.remove();
-        if let Err(err) = self.sender.send(()) {
-            error!("unwatch signal not sent - {}", err);
-        }
+        self.listener.remove();
+        self.wakeup.wake();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merge_all(writes: &[WriteType]) -> Option<WriteType> {
+        writes.iter().fold(None, |pending, write| Some(merge_writes(pending, *write)))
+    }
+
+    #[test]
+    fn merged_writes() {
+        assert_eq!(merge_all(&[]), None);
+        assert_eq!(merge_all(&[WriteType::Theme, WriteType::Theme]), Some(WriteType::Theme));
+        assert_eq!(merge_all(&[WriteType::Theme, WriteType::Config]), Some(WriteType::Config));
+        assert_eq!(merge_all(&[WriteType::Config, WriteType::Theme]), Some(WriteType::Config));
+        assert_eq!(merge_all(&[WriteType::Config, WriteType::Config]), Some(WriteType::Config));
     }
 }
@@ -1,5 +1,8 @@
+use gtk::prelude::*;
+
 use crate::wm;
 use crate::wm::ipc::commands::Selectors;
+use crate::wm::ipc::selector::Element;
 use crate::components::Component;
 use crate::config::ConfigGroup;
 
@@ -8,9 +11,31 @@ pub trait Window {
     fn get_container(&self) -> &gtk::Box;
     fn get_overlay(&self) -> &gtk::Overlay;
     fn get_monitor_index(&self) -> usize;
+    fn get_config(&self) -> &ConfigGroup;
+    fn get_type(&self) -> &'static str;
+    fn is_visible(&self) -> bool;
     fn add_component(&mut self, _: Box<dyn Component>);
-    fn matches_selectors(&self, _: &Selectors) -> bool;
     fn load_component(&mut self, config: ConfigGroup, container: &gtk::Box, wm_util: &wm::WMUtil);
+    fn refresh_components(&self, names: &[String]) -> usize;
+
+    /// matched by name, `class` option, current classes, type and monitor
+    fn get_element(&self) -> Element {
+        let config = self.get_config();
+        let mut classes = wm::gtk::get_style_classes(self.get_container().upcast_ref());
+        classes.extend(config.get_string("class"));
+        Element {
+            name: config.name.clone(),
+            classes,
+            attributes: vec![
+                ("type", self.get_type().to_string()),
+                ("monitor", self.get_monitor_index().to_string()),
+            ],
+        }
+    }
+
+    fn matches_selectors(&self, selectors: &Selectors) -> bool {
+        selectors.matches(&[self.get_element()])
+    }
 
     // lifecycle
     // fn move_(&self) {}
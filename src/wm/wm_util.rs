@@ -1,9 +1,10 @@
 use crate::bar::Bar;
 use crate::float::Float;
-use crate::config::{Args, Config, ConfigGroup, parse_file};
+use crate::config::{Args, Config, ConfigGroup, Property, parse_file};
 use crate::wm::events::{Event, EventEmitter, EventId, EventValue};
 use crate::wm::ipc::parser::parse_message;
 use crate::wm::ipc::commands::*;
+use crate::wm::ipc::selector::Element;
 use crate::wm::workspace::Workspace;
 use crate::wm::watch::Watcher;
 use crate::wm::Window as _;
@@ -14,6 +15,7 @@ use gtk::prelude::*;
 use gtk::CssProvider;
 
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::fmt;
 use std::rc::Rc;
 
@@ -50,19 +52,7 @@ impl WMUtil {
         app: gtk::Application, config: Config, args: &Args
     ) -> Self {
         let wm_name = wm::xcb::get_wm_name();
-        let wm_type = match wm_name.as_str() {
-            "bspwm" => {
-                if wm::bsp::connect().is_ok() {
-                    WMType::Bsp
-                } else {
-                    error!("found bspwm but failed to get a connection. \
-                        try setting BSPWM_SOCKET");
-                    WMType::Unknown
-                }
-            },
-            "i3" => WMType::I3,
-            _ => WMType::Unknown,
-        };
+        let wm_type = get_wm_type(&wm_name);
 
         if wm_type != WMType::Unknown {
             info!("using {}wm extensions", wm_type);
@@ -86,9 +76,11 @@ impl WMUtil {
         let util = WMUtil { data, windows };
 
         // start IPC
-        if util.data.borrow().config.global.get_bool_or("enable-ipc", true) {
-            wm::ipc::listen(&util);
-        }
+        let (enable_ipc, enable_dbus) = {
+            let global = &util.data.borrow().config.global;
+            (global.get_bool_or("enable-ipc", true), global.get_bool_or("enable-dbus", false))
+        };
+        wm::ipc::listen(&util, args.get_instance(), enable_ipc, enable_dbus);
 
         // listen for WM events
         wm::xcb::listen(&util);
@@ -109,27 +101,38 @@ impl WMUtil {
         }
 
         wm::gtk::css_reset();
-        util.load_theme(None);
+        util.load_theme(None).ok();
         util.load_windows();
+        wm::gtk::listen_monitors(&util);
         if args.watch {
-            util.watch_files();
+            if let Err(err) = util.watch_files() {
+                error!("{}", err);
+            }
         }
 
         util
     }
 
+    pub fn get_app(&self) -> gtk::Application {
+        self.data.borrow().app.clone()
+    }
+
     pub fn add_gtk_window(&self, window: &gtk::Window) {
         self.data.borrow().app.add_window(window);
     }
 
     pub fn run_command(&self, cmd: &str) {
         if cmd.starts_with(":") {
-            match parse_message(&cmd[1..]) {
-                Ok(cmd) => {
-                    wm::ipc::exec::run_command(self, cmd);
+            match parse_message(&cmd[1..], &self.get_aliases()) {
+                Ok(steps) => {
+                    for outcome in wm::ipc::exec::run_sequence(self, steps) {
+                        if let Some(Err(err)) = outcome.result {
+                            error!("{}", err);
+                        }
+                    }
                 },
-                Err(_) => {
-                    error!("problem parsing command {}", cmd);
+                Err(err) => {
+                    error!("problem parsing command {}: {}", cmd, err);
                 },
             }
         } else {
@@ -137,25 +140,34 @@ impl WMUtil {
         }
     }
 
-    pub fn watch_files(&self) {
-        let watcher = Watcher::new(self, &self.data.borrow().config);
+    pub fn watch_files(&self) -> Result<(), String> {
+        let watcher = Watcher::new(self, &self.data.borrow().config)?;
         self.data.borrow_mut().watcher = Some(watcher);
+        Ok(())
+    }
+
+    pub fn unwatch_files(&self) {
+        if let Some(watcher) = self.data.borrow_mut().watcher.take() {
+            watcher.unwatch();
+        }
+    }
+
+    /// the files being watched, if watching
+    pub fn get_watched_files(&self) -> Option<Vec<String>> {
+        self.data.borrow().watcher.as_ref().map(|watcher| watcher.get_files().to_vec())
     }
 
     pub fn rewatch_files(&self) {
-        let was_some = {
-            let watcher_opt = &self.data.borrow().watcher;
-            if let Some(watcher) = watcher_opt {
-                watcher.unwatch();
+        let watcher_opt = self.data.borrow_mut().watcher.take();
+        if let Some(watcher) = watcher_opt {
+            watcher.unwatch();
+            if let Err(err) = self.watch_files() {
+                error!("{}", err);
             }
-            watcher_opt.is_some()
-        };
-        if was_some {
-            self.watch_files();
         }
     }
 
-    pub fn reload_config(&self, new_path: Option<String>) {
+    pub fn reload_config(&self, new_path: Option<String>) -> Result<(), String> {
         let change_config = new_path.is_some();
         // update filename
         if let Some(new_path) = new_path {
@@ -164,26 +176,35 @@ impl WMUtil {
         // get filename
         let filename = self.data.borrow().config.get_filename();
         // load config
-        let config_res = parse_file(&filename);
-        if let Ok(config) = config_res {
-            // update config
-            self.data.borrow_mut().config = config;
-            if change_config {
-                // unload old windows if changing the config
-                self.windows.borrow_mut().iter().for_each(|b| b.destroy());
-                self.windows.borrow_mut().clear();
-                // watch different files
-                self.rewatch_files();
-            }
-            // reload everything
-            self.load_theme(None);
-            self.load_windows();
-        } else if let Err(msg) = config_res {
-            error!("{}", msg);
+        let config = match parse_file(&filename) {
+            Ok(config) => config,
+            Err(msg) => {
+                error!("{}", msg);
+                return Err(msg);
+            },
+        };
+        for err in config.validate() {
+            warn!("{}", err);
+        }
+        // update config
+        let includes_changed = self.data.borrow().config.includes != config.includes;
+        self.data.borrow_mut().config = config;
+        if change_config {
+            // unload old windows if changing the config
+            self.windows.borrow_mut().iter().for_each(|b| b.destroy());
+            self.windows.borrow_mut().clear();
+        }
+        if change_config || includes_changed {
+            // watch different files
+            self.rewatch_files();
         }
+        // reload everything
+        let theme_res = self.load_theme(None);
+        self.load_windows();
+        theme_res
     }
 
-    pub fn load_theme(&self, new_path: Option<String>) {
+    pub fn load_theme(&self, new_path: Option<String>) -> Result<(), String> {
         // unload old theme
         if let Some(ref provider) = self.data.borrow().css_provider {
             wm::gtk::unload_theme(provider);
@@ -199,14 +220,16 @@ impl WMUtil {
         match wm::gtk::load_theme(&theme) {
             Ok(provider) => {
                 self.data.borrow_mut().css_provider = Some(provider);
+                Ok(())
             }
             Err(err) => {
                 error!("{}", err);
+                Err(err.to_string())
             }
         }
     }
 
-    fn load_windows(&self) {
+    pub fn load_windows(&self) {
         // unload old windows and retain gtk::Window
         let windows = self.windows.borrow_mut().split_off(0);
         let mut gtk_windows: Vec<gtk::Window> =
@@ -218,35 +241,42 @@ impl WMUtil {
                 {
                     let windows: Vec<Box<dyn wm::Window>> =
                         $config_list.iter().fold(Vec::new(), |mut acc, win_config| {
-                            let monitor_index = win_config.get_int_or("monitor", 0);
-                            let monitor_option = $monitors.get(monitor_index as usize);
-
-                            if let Some(monitor) = monitor_option {
-                                let mut window = $constructor(
-                                    win_config.clone(),
-                                    &self,
-                                    monitor,
-                                    gtk_windows.pop(),
-                                );
-
-                                // load components
-                                let container = &window.get_container().clone();
-                                for name in win_config.get_string_vec("layout") {
-                                    let config_opt = self.get_component_config(&name);
-                                    if let Some(config) = config_opt {
-                                        window.load_component(
-                                            config,
-                                            container,
-                                            &self
-                                        );
-                                    } else {
-                                        warn!("missing component #{}", name);
+                            for monitor_index in get_monitor_indices(win_config) {
+                                let monitor_option = $monitors.get(monitor_index as usize);
+
+                                if let Some(monitor) = monitor_option {
+                                    // each instance only knows about its own monitor
+                                    let mut instance_config = win_config.clone();
+                                    instance_config.properties.insert(
+                                        "monitor".to_string(),
+                                        Property::Integer(monitor_index as i64),
+                                    );
+                                    let mut window = $constructor(
+                                        instance_config,
+                                        &self,
+                                        monitor,
+                                        gtk_windows.pop(),
+                                    );
+
+                                    // load components
+                                    let container = &window.get_container().clone();
+                                    for name in win_config.get_string_vec("layout") {
+                                        let config_opt = self.get_component_config(&name);
+                                        if let Some(config) = config_opt {
+                                            window.load_component(
+                                                config,
+                                                container,
+                                                &self
+                                            );
+                                        } else {
+                                            warn!("missing component #{}", name);
+                                        }
                                     }
-                                }
 
-                                acc.push(Box::new(window));
-                            } else {
-                                warn!("no monitor at index {}", monitor_index);
+                                    acc.push(Box::new(window));
+                                } else {
+                                    warn!("no monitor at index {}", monitor_index);
+                                }
                             }
                             acc
                         });
@@ -282,18 +312,6 @@ impl WMUtil {
         self.windows.borrow_mut().append(&mut floats);
     }
 
-    pub fn display_windows(&self, names: &Selectors, show: bool) {
-        for bar in self.windows.borrow().iter() {
-            if bar.matches_selectors(names) {
-                if show {
-                    bar.show();
-                } else {
-                    bar.hide();
-                }
-            }
-        }
-    }
-
     // getters
 
     pub fn get_wm_type(&self) -> WMType {
@@ -306,8 +324,82 @@ impl WMUtil {
         }).cloned()
     }
 
-    pub fn get_path(&self, filename: &str) -> String {
-        self.data.borrow().config.get_path(filename)
+    pub fn get_component_names(&self) -> Vec<String> {
+        self.data.borrow().config.components.iter().map(|x| x.name.clone()).collect()
+    }
+
+    pub fn get_matching_components(&self, selectors: &Selectors) -> Vec<(String, gtk::Widget)> {
+        self.map_components(|_window, name, widget, path| {
+            if selectors.matches(path) {
+                Some((name, widget))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// call back with each component, its window and the elements from
+    /// its window down to itself for matching selectors against
+    pub fn map_components<T, F>(&self, callback: F) -> Vec<T>
+        where F: Fn(&dyn wm::Window, String, gtk::Widget, &[Element]) -> Option<T>
+    {
+        let names = self.get_component_names();
+        self.map_windows(|window| {
+            let root = window.get_overlay().clone().upcast::<gtk::Widget>();
+            let window_widgets = vec![window.get_container().clone().upcast::<gtk::Widget>()];
+            let window_element = window.get_element();
+            wm::gtk::find_components(&root, &window_widgets, &names)
+                .into_iter()
+                .filter_map(|components| {
+                    let mut path = vec![window_element.clone()];
+                    for (name, widget) in &components {
+                        path.push(self.get_component_element(name.clone(), widget, window));
+                    }
+                    let (name, widget) = components.last()?.clone();
+                    callback(window, name, widget, &path)
+                })
+                .collect::<Vec<T>>()
+        })
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    /// matched by name, current classes, type and the monitor of its window
+    fn get_component_element(
+        &self, name: String, widget: &gtk::Widget, window: &dyn wm::Window
+    ) -> Element {
+        let type_ = self.get_component_config(&name)
+            .and_then(|config| config.get_string("type"))
+            .unwrap_or_default();
+        Element {
+            name,
+            classes: wm::gtk::get_style_classes(widget),
+            attributes: vec![
+                ("type", type_),
+                ("monitor", window.get_monitor_index().to_string()),
+            ],
+        }
+    }
+
+    pub fn get_aliases(&self) -> HashMap<String, String> {
+        self.data.borrow().config.aliases.properties.iter()
+            .filter_map(|(name, property)| match property {
+                Property::String(command) => Some((name.clone(), command.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn get_resolved_config(&self) -> toml::value::Table {
+        crate::config::get_resolved(&self.data.borrow().config)
+    }
+
+    pub fn map_windows<T, F>(&self, callback: F) -> Vec<T>
+    where
+        F: Fn(&dyn wm::Window) -> T,
+    {
+        self.windows.borrow().iter().map(|window| callback(window.as_ref())).collect()
     }
 
     // events
@@ -343,14 +435,10 @@ impl WMUtil {
                 Ok(mut connection) => Some(wm::bsp::get_workspaces(&mut connection)),
                 Err(_) => None,
             },
-            WMType::Unknown => match wm::xcb::connect_ewmh() {
-                Ok((connection, screen_num)) => {
+            WMType::Unknown => match wm::xcb::get_connection() {
+                Ok(connection) => {
                     let monitors = wm::gtk::get_monitor_coords();
-                    Some(wm::xcb::get_workspaces(
-                        &connection,
-                        screen_num,
-                        &monitors
-                    ))
+                    Some(wm::xcb::get_workspaces(&connection, &monitors))
                 },
                 Err(_) => None,
             },
@@ -367,13 +455,9 @@ impl WMUtil {
                 let command = format!("desktop -f {}", workspace_name);
                 wm::bsp::run_command(command).ok();
             }
-            WMType::Unknown => match wm::xcb::connect_ewmh() {
-                Ok((connection, screen_num)) => {
-                    wm::xcb::focus_workspace(
-                        &connection,
-                        screen_num,
-                        workspace_name,
-                    );
+            WMType::Unknown => match wm::xcb::get_connection() {
+                Ok(connection) => {
+                    wm::xcb::focus_workspace(&connection, workspace_name);
                 },
                 Err(err) => error!("{}", err),
             },
@@ -400,3 +484,35 @@ impl WMUtil {
         }
     }
 }
+
+/// decide which extensions to use for a window manager
+pub fn get_wm_type(wm_name: &str) -> WMType {
+    match wm_name {
+        "bspwm" => {
+            if wm::bsp::connect().is_ok() {
+                WMType::Bsp
+            } else {
+                error!("found bspwm but failed to get a connection. \
+                    try setting BSPWM_SOCKET");
+                WMType::Unknown
+            }
+        },
+        "i3" => WMType::I3,
+        _ => WMType::Unknown,
+    }
+}
+
+/// resolve a window's monitor property to monitor indexes
+fn get_monitor_indices(config: &ConfigGroup) -> Vec<i32> {
+    match config.properties.get("monitor") {
+        Some(Property::Integer(index)) => vec![*index as i32],
+        Some(Property::String(query)) => {
+            let indices = wm::gtk::get_monitor_indices(query);
+            if indices.is_empty() {
+                warn!("no monitor matching {:?} for #{}", query, config.name);
+            }
+            indices
+        },
+        _ => vec![0],
+    }
+}
@@ -1,7 +1,7 @@
 use i3ipc::reply::Workspace as I3Workspace;
 use crate::wm;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize)]
 pub struct Workspace {
     pub number: i32,
     pub name: String,
@@ -0,0 +1,307 @@
+use crate::wm::atom::{self, Atoms};
+use crossbeam_channel as channel;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// in 32 bit units
+const PROPERTY_LENGTH: u32 = 1024;
+/// windows can be destroyed before requests about them arrive
+const BAD_WINDOW: u8 = 3;
+
+lazy_static! {
+    static ref CONNECTION: Mutex<Option<Arc<XConnection>>> = Mutex::new(None);
+}
+
+/// connect the first time, or again if the connection was lost
+pub fn get_connection() -> Result<Arc<XConnection>, String> {
+    let mut connection = CONNECTION.lock().unwrap();
+    if let Some(conn) = connection.as_ref() {
+        if conn.has_error().is_ok() {
+            return Ok(conn.clone());
+        }
+    }
+    let conn = XConnection::connect()?;
+    *connection = Some(conn.clone());
+    Ok(conn)
+}
+
+/// the X connection shared by everything, with one thread reading events
+pub struct XConnection {
+    conn: xcb::Connection,
+    screen_num: i32,
+    atoms: Atoms,
+    subscribers: Mutex<Vec<Subscriber>>,
+    /// keeps event mask requests in the order they were worked out, without
+    /// holding up dispatch while they're sent
+    mask_updates: Mutex<()>,
+    next_id: AtomicUsize,
+}
+
+struct Subscriber {
+    id: usize,
+    sender: channel::Sender<xcb::GenericEvent>,
+    /// windows and the events wanted from them
+    windows: HashMap<xcb::Window, u32>,
+}
+
+impl Deref for XConnection {
+    type Target = xcb::Connection;
+
+    fn deref(&self) -> &xcb::Connection {
+        &self.conn
+    }
+}
+
+impl XConnection {
+    fn connect() -> Result<Arc<XConnection>, String> {
+        let (conn, screen_num) = xcb::Connection::connect(None)
+            .map_err(|_| "could not connect to X server".to_string())?;
+        let atoms = Atoms::new(&conn);
+        let conn = Arc::new(XConnection {
+            conn,
+            screen_num,
+            atoms,
+            subscribers: Mutex::new(Vec::new()),
+            mask_updates: Mutex::new(()),
+            next_id: AtomicUsize::new(0),
+        });
+        thread::spawn(clone!(conn move || conn.dispatch()));
+        Ok(conn)
+    }
+
+    pub fn atoms(&self) -> &Atoms {
+        &self.atoms
+    }
+
+    pub fn get_screen_num(&self) -> i32 {
+        self.screen_num
+    }
+
+    pub fn get_screen(&self) -> xcb::Screen {
+        self.conn.get_setup().roots().nth(self.screen_num as usize).unwrap()
+    }
+
+    pub fn get_root(&self) -> xcb::Window {
+        self.get_screen().root()
+    }
+
+    pub fn get_property<T: Clone>(
+        &self,
+        window: xcb::Window,
+        name: &str,
+        type_: xcb::Atom,
+    ) -> Vec<T> {
+        xcb::get_property(
+            &self.conn,
+            false,
+            window,
+            self.atoms.get(name),
+            type_,
+            0,
+            PROPERTY_LENGTH,
+        )
+            .get_reply()
+            .map(|reply| reply.value::<T>().to_vec())
+            .unwrap_or_else(|_| Vec::new())
+    }
+
+    pub fn get_window_property(&self, window: xcb::Window, name: &str) -> Option<xcb::Window> {
+        self.get_property(window, name, xcb::ATOM_WINDOW).first().cloned()
+    }
+
+    pub fn get_string_property(&self, window: xcb::Window, name: &str) -> Vec<String> {
+        let bytes = self.get_property::<u8>(window, name, self.atoms.get(atom::UTF8_STRING));
+        if bytes.is_empty() {
+            return Vec::new();
+        }
+        String::from_utf8_lossy(&bytes)
+            .trim_end_matches('\0')
+            .split('\0')
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// the EWMH name, falling back to ICCCM
+    pub fn get_window_name(&self, window: xcb::Window) -> String {
+        match self.get_string_property(window, atom::_NET_WM_NAME).into_iter().next() {
+            Some(name) => name,
+            None => match xcb_util::icccm::get_wm_name(&self.conn, window).get_reply() {
+                Ok(reply) => reply.name().to_string(),
+                Err(_) => "".to_string(),
+            },
+        }
+    }
+
+    fn dispatch(&self) {
+        while let Some(event) = self.conn.wait_for_event() {
+            let response_type = event.response_type() & !0x80;
+            if response_type == 0 {
+                let error: &xcb::GenericError = unsafe { xcb::cast_event(&event) };
+                if error.error_code() != BAD_WINDOW {
+                    warn!("X error {}", error.error_code());
+                }
+                continue;
+            }
+            let window = match get_event_window(&event, response_type) {
+                Some(window) => window,
+                None => continue,
+            };
+            let mut subscribers = self.subscribers.lock().unwrap();
+            for subscriber in subscribers.iter() {
+                if subscriber.windows.contains_key(&window) {
+                    subscriber.sender.send(copy_event(&event)).ok();
+                }
+            }
+            // destroyed windows can't be unwatched
+            if response_type == xcb::DESTROY_NOTIFY {
+                let event: &xcb::DestroyNotifyEvent = unsafe { xcb::cast_event(&event) };
+                for subscriber in subscribers.iter_mut() {
+                    subscriber.windows.remove(&event.window());
+                }
+            }
+        }
+        error!("lost connection to X server");
+        // dropping the senders ends every subscription
+        self.subscribers.lock().unwrap().clear();
+    }
+
+    fn set_event_mask(&self, window: xcb::Window, mask: u32) {
+        xcb::change_window_attributes(&self.conn, window, &[(xcb::CW_EVENT_MASK, mask)]);
+        self.conn.flush();
+    }
+}
+
+/// every subscriber's events for a window have to be selected at once
+fn get_event_mask(subscribers: &[Subscriber], window: xcb::Window) -> u32 {
+    subscribers.iter()
+        .filter_map(|subscriber| subscriber.windows.get(&window))
+        .fold(xcb::EVENT_MASK_NO_EVENT, |mask, window_mask| mask | window_mask)
+}
+
+/// the window an event was selected on
+fn get_event_window(event: &xcb::GenericEvent, response_type: u8) -> Option<xcb::Window> {
+    unsafe {
+        match response_type {
+            xcb::PROPERTY_NOTIFY => {
+                Some(xcb::cast_event::<xcb::PropertyNotifyEvent>(event).window())
+            },
+            xcb::CLIENT_MESSAGE => {
+                Some(xcb::cast_event::<xcb::ClientMessageEvent>(event).window())
+            },
+            xcb::VISIBILITY_NOTIFY => {
+                Some(xcb::cast_event::<xcb::VisibilityNotifyEvent>(event).window())
+            },
+            xcb::SELECTION_CLEAR => {
+                Some(xcb::cast_event::<xcb::SelectionClearEvent>(event).owner())
+            },
+            xcb::MAP_NOTIFY => Some(xcb::cast_event::<xcb::MapNotifyEvent>(event).event()),
+            xcb::UNMAP_NOTIFY => Some(xcb::cast_event::<xcb::UnmapNotifyEvent>(event).event()),
+            xcb::DESTROY_NOTIFY => {
+                Some(xcb::cast_event::<xcb::DestroyNotifyEvent>(event).event())
+            },
+            xcb::REPARENT_NOTIFY => {
+                Some(xcb::cast_event::<xcb::ReparentNotifyEvent>(event).event())
+            },
+            xcb::CONFIGURE_NOTIFY => {
+                Some(xcb::cast_event::<xcb::ConfigureNotifyEvent>(event).event())
+            },
+            _ => None,
+        }
+    }
+}
+
+/// events are freed when dropped, so each subscriber gets its own copy
+fn copy_event(event: &xcb::GenericEvent) -> xcb::GenericEvent {
+    unsafe {
+        let size = std::mem::size_of::<xcb::ffi::xcb_generic_event_t>();
+        let ptr = libc::malloc(size) as *mut xcb::ffi::xcb_generic_event_t;
+        std::ptr::copy_nonoverlapping(event.ptr, ptr, 1);
+        xcb::GenericEvent { ptr }
+    }
+}
+
+/// events from the windows being watched, until dropped
+pub struct Subscription {
+    id: usize,
+    conn: Arc<XConnection>,
+    events: channel::Receiver<xcb::GenericEvent>,
+}
+
+impl Subscription {
+    pub fn new(conn: &Arc<XConnection>) -> Subscription {
+        let id = conn.next_id.fetch_add(1, Ordering::SeqCst);
+        let (sender, events) = channel::unbounded();
+        conn.subscribers.lock().unwrap().push(Subscriber {
+            id,
+            sender,
+            windows: HashMap::new(),
+        });
+        Subscription { id, conn: conn.clone(), events }
+    }
+
+    pub fn get_receiver(&self) -> &channel::Receiver<xcb::GenericEvent> {
+        &self.events
+    }
+
+    /// blocks until there's an event, or the connection is lost
+    pub fn wait_for_event(&self) -> Option<xcb::GenericEvent> {
+        self.events.recv().ok()
+    }
+
+    /// receive events from a window, on top of what others want from it
+    pub fn watch(&self, window: xcb::Window, mask: u32) {
+        self.update(window, |windows| {
+            *windows.entry(window).or_insert(xcb::EVENT_MASK_NO_EVENT) |= mask;
+            true
+        });
+    }
+
+    pub fn unwatch(&self, window: xcb::Window) {
+        self.update(window, |windows| windows.remove(&window).is_some());
+    }
+
+    /// the callback returns whether the windows changed
+    fn update<F>(&self, window: xcb::Window, callback: F)
+    where
+        F: FnOnce(&mut HashMap<xcb::Window, u32>) -> bool,
+    {
+        let _mask_updates = self.conn.mask_updates.lock().unwrap();
+        let mask = {
+            let mut subscribers = self.conn.subscribers.lock().unwrap();
+            let changed = subscribers.iter_mut()
+                .find(|subscriber| subscriber.id == self.id)
+                .map_or(false, |subscriber| callback(&mut subscriber.windows));
+            if !changed {
+                return;
+            }
+            get_event_mask(&subscribers, window)
+        };
+        self.conn.set_event_mask(window, mask);
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        let _mask_updates = self.conn.mask_updates.lock().unwrap();
+        let masks = {
+            let mut subscribers = self.conn.subscribers.lock().unwrap();
+            let index = subscribers.iter().position(|subscriber| subscriber.id == self.id);
+            match index {
+                Some(index) => {
+                    let subscriber = subscribers.remove(index);
+                    subscriber.windows.keys()
+                        .map(|window| (*window, get_event_mask(&subscribers, *window)))
+                        .collect::<Vec<_>>()
+                },
+                None => return,
+            }
+        };
+        for (window, mask) in masks {
+            self.conn.set_event_mask(window, mask);
+        }
+    }
+}
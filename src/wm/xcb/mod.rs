@@ -1,90 +1,36 @@
+mod connection;
 mod listen;
 mod workspaces;
 pub mod xwindows;
 
+pub use self::connection::*;
 pub use self::listen::listen;
 pub use self::workspaces::*;
 
 use crate::wm::atom;
-use xcb_util::ewmh;
-
-pub fn connect_ewmh() -> Result<(ewmh::Connection, i32), &'static str> {
-    let (connection, screen_num) = xcb::Connection::connect(None)
-        .map_err(|_| "could not connect to X server")?;
-    let connection = ewmh::Connection::connect(connection)
-        .map_err(|_| "cannot get EWMH connection")?;
-    Ok((connection, screen_num))
-}
 
 pub fn get_wm_name() -> String {
-    let default = "".to_string();
-    let (conn, screen_num) = match connect_ewmh() {
-        Ok(c) => c,
-        Err(_) => return default,
-    };
-    let root = conn.get_setup().roots().nth(screen_num as usize).unwrap().root();
-    let wm_check = xcb_util::ewmh::get_supporting_wm_check(&conn, root).get_reply();
-    let window = match wm_check {
-        Ok(window) => window,
-        Err(_) => return default,
+    let conn = match get_connection() {
+        Ok(conn) => conn,
+        Err(_) => return "".to_string(),
     };
-
-    match xcb_util::ewmh::get_wm_name(&conn, window).get_reply() {
-        Ok(reply) => reply.string().to_string(),
-        Err(_) => {
-            match xcb_util::icccm::get_wm_name(&conn, window).get_reply() {
-                Ok(reply) => reply.name().to_string(),
-                Err(_) => default,
-            }
-        },
+    match conn.get_window_property(conn.get_root(), atom::_NET_SUPPORTING_WM_CHECK) {
+        Some(window) => conn.get_window_name(window),
+        None => "".to_string(),
     }
 }
 
-pub fn check_fullscreen(conn: &xcb::Connection, atoms: &atom::Atoms, screen: &xcb::Screen) -> bool {
+pub fn check_fullscreen(conn: &XConnection) -> bool {
     // get active window
-    let cookie = xcb::get_property(
-        &conn,
-        false,
-        screen.root(),
-        atoms.get(atom::_NET_ACTIVE_WINDOW),
-        xcb::ATOM_WINDOW,
-        0,
-        8,
-    );
-
-    match cookie.get_reply() {
-        Ok(reply) => {
-            let value: &[u32] = reply.value();
-            if value.is_empty() {
-                return false;
-            }
-            // get wm state
-            let cookie = xcb::get_property(
-                &conn,
-                false,
-                value[0],
-                atoms.get(atom::_NET_WM_STATE),
-                xcb::ATOM_ATOM,
-                0,
-                8,
-            );
-            match cookie.get_reply() {
-                Ok(reply) => {
-                    // check if active window is fullscreen
-                    let value: &[u32] = reply.value();
-                    let fullscreen_atom = atoms.get(atom::_NET_WM_STATE_FULLSCREEN);
-                    for v in value.iter() {
-                        if *v == fullscreen_atom {
-                            return true;
-                        }
-                    }
-                }
-                Err(_err) => {}
-            }
-        }
-        Err(_err) => {}
+    match conn.get_window_property(conn.get_root(), atom::_NET_ACTIVE_WINDOW) {
+        Some(window) => {
+            // check if active window is fullscreen
+            let fullscreen_atom = conn.atoms().get(atom::_NET_WM_STATE_FULLSCREEN);
+            conn.get_property::<xcb::Atom>(window, atom::_NET_WM_STATE, xcb::ATOM_ATOM)
+                .contains(&fullscreen_atom)
+        },
+        None => false,
     }
-    false
 }
 
 // pub fn get_string(conn: &xcb::Connection, id: u32, _type: u32, attr: u32) -> String {
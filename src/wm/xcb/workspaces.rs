@@ -1,24 +1,25 @@
-use xcb_util::ewmh;
 use crate::wm;
+use crate::wm::atom;
 use crate::wm::workspace::Workspace;
+use crate::wm::xcb::XConnection;
 
-pub fn focus_workspace(
-    conn: &ewmh::Connection,
-    screen_num: i32,
-    workspace_name: &str,
-) {
-    let names_reply = ewmh::get_desktop_names(&conn, screen_num).get_reply();
-    let names = match &names_reply {
-        Ok(r) => r.strings(),
-        Err(_) => Vec::new(),
-    };
-    let index = names.iter().position(|s| s == &workspace_name);
+pub fn focus_workspace(conn: &XConnection, workspace_name: &str) {
+    let root = conn.get_root();
+    let names = conn.get_string_property(root, atom::_NET_DESKTOP_NAMES);
+    let index = names.iter().position(|s| s == workspace_name);
     if let Some(index) = index {
-        let desktop_check = ewmh::request_change_current_desktop(
-            &conn,
-            screen_num,
-            index as u32,
-            0,
+        let client_event = xcb::ClientMessageEvent::new(
+            32,
+            root,
+            conn.atoms().get(atom::_NET_CURRENT_DESKTOP),
+            xcb::ClientMessageData::from_data32([index as u32, 0, 0, 0, 0]),
+        );
+        let desktop_check = xcb::send_event_checked(
+            conn,
+            false,
+            root,
+            xcb::EVENT_MASK_SUBSTRUCTURE_NOTIFY | xcb::EVENT_MASK_SUBSTRUCTURE_REDIRECT,
+            &client_event,
         ).request_check();
         if desktop_check.is_err() {
             warn!("failed to set workspace to {}", workspace_name);
@@ -29,26 +30,20 @@ pub fn focus_workspace(
 }
 
 pub fn get_workspaces(
-    conn: &ewmh::Connection,
-    screen_num: i32,
+    conn: &XConnection,
     monitors: &Vec<(i32, i32, String)>,
 ) -> Vec<Workspace> {
-    let current = ewmh::get_current_desktop(&conn, screen_num)
-        .get_reply()
+    let root = conn.get_root();
+    let current = conn.get_property::<u32>(root, atom::_NET_CURRENT_DESKTOP, xcb::ATOM_CARDINAL)
+        .first()
+        .cloned()
         .unwrap_or(0) as usize;
-    let names_reply = ewmh::get_desktop_names(&conn, screen_num).get_reply();
-    let names = match &names_reply {
-        Ok(r) => r.strings(),
-        Err(_) => Vec::new(),
-    };
+    let names = conn.get_string_property(root, atom::_NET_DESKTOP_NAMES);
 
-    let viewports_reply = ewmh::get_desktop_viewport(&conn, screen_num).get_reply();
-
-    let viewports = match &viewports_reply {
-        Ok(r) => r.desktop_viewports().iter()
-            .map(|vp| (vp.x as i32, vp.y as i32)).collect(),
-        Err(_) => Vec::new(),
-    };
+    let viewports = conn.get_property::<u32>(root, atom::_NET_DESKTOP_VIEWPORT, xcb::ATOM_CARDINAL)
+        .chunks_exact(2)
+        .map(|vp| (vp[0] as i32, vp[1] as i32))
+        .collect::<Vec<_>>();
 
     let fallback_monitor = (0, 0, "[unknown]".to_string());
 
@@ -85,15 +80,15 @@ pub fn get_workspaces(
 }
 
 pub fn cycle_workspace(forward: bool, monitor_index: i32) {
-    match wm::xcb::connect_ewmh() {
-        Ok((conn, screen_num)) => {
+    match wm::xcb::get_connection() {
+        Ok(conn) => {
             let monitors = wm::gtk::get_monitor_coords();
-            let workspaces = get_workspaces(&conn, screen_num, &monitors);
+            let workspaces = get_workspaces(&conn, &monitors);
 
             let next_opt = wm::workspace::get_next(&workspaces, forward, monitor_index);
 
             if let Some(next) = next_opt {
-                focus_workspace(&conn, screen_num, &next.name);
+                focus_workspace(&conn, &next.name);
             }
         }
         Err(err) => {
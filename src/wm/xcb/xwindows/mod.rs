@@ -1,11 +1,11 @@
-use std::sync::mpsc;
 use std::thread;
 use std::collections::HashMap;
 
+use crate::util;
 use crate::wm;
 use crate::wm::events::{Event, EventValue};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, serde::Serialize)]
 pub struct XWindowData {
     pub x: i16,
     pub y: i16,
@@ -18,51 +18,45 @@ pub struct XWindowData {
 const GEOMETRY_NOTIFY: u8 = 150;
 
 pub fn listen(wm_util: &crate::wm::WMUtil) {
-    let (tx, rx) = mpsc::channel();
+    let (tx, rx) = util::channel();
 
     thread::spawn(move || {
-        match xcb::Connection::connect(None) {
-            Ok((conn, screen_num)) => {
+        match wm::xcb::get_connection() {
+            Ok(conn) => {
 
-                let screen = conn.get_setup().roots().nth(screen_num as usize).unwrap();
-                let atoms = wm::atom::Atoms::new(&conn);
+                let atoms = conn.atoms();
+                let events = wm::xcb::Subscription::new(&conn);
 
                 let mut windows = HashMap::new();
 
-                for window in get_client_list(&conn, &atoms, &screen) {
-                    windows.insert(window, add_window(&conn, window));
+                for window in get_client_list(&conn) {
+                    windows.insert(window, add_window(&conn, &events, window));
                 }
                 // init windows
                 tx.send(Ok(windows.clone())).unwrap();
 
-                xcb::change_window_attributes(
-                    &conn,
-                    screen.root(),
-                    &[(xcb::CW_EVENT_MASK, xcb::EVENT_MASK_SUBSTRUCTURE_NOTIFY)],
-                );
-
-                conn.flush();
+                events.watch(conn.get_root(), xcb::EVENT_MASK_SUBSTRUCTURE_NOTIFY);
 
                 loop {
-                    match conn.wait_for_event() {
+                    match events.wait_for_event() {
                         Some(event) => {
                             match event.response_type() {
                                 xcb::MAP_NOTIFY => {
-                                    let clients = get_client_list(&conn, &atoms, &screen);
+                                    let clients = get_client_list(&conn);
                                     let new_clients = clients.iter()
                                         .filter(|c| !windows.keys().any(|k| &k == c))
                                         .collect::<Vec<_>>();
 
                                     if !new_clients.is_empty() {
                                         for window in new_clients {
-                                            windows.insert(*window, add_window(&conn, *window));
+                                            windows.insert(*window, add_window(&conn, &events, *window));
                                             // add window
                                         }
                                         tx.send(Ok(windows.clone())).unwrap();
                                     }
                                 },
                                 xcb::DESTROY_NOTIFY => {
-                                    let clients = get_client_list(&conn, &atoms, &screen);
+                                    let clients = get_client_list(&conn);
                                     let removed_clients = windows.keys()
                                         .filter(|c| !clients.iter().any(|k| &k == c))
                                         .map(|c| c.clone())
@@ -150,25 +144,23 @@ pub fn listen(wm_util: &crate::wm::WMUtil) {
         }
     });
 
-    gtk::timeout_add(10, clone!(wm_util move || {
-        while let Ok(windows_result) = rx.try_recv() {
-            match windows_result {
-                Ok(windows) => {
-                    wm_util.emit_value(
-                        Event::Windows,
-                        EventValue::Windows(windows),
-                    );
-                },
-                Err(err) => {
-                    warn!("{}, restarting thread", err.to_lowercase());
-                    gtk::timeout_add(1000, clone!(wm_util move || {
-                        listen(&wm_util);
-                        gtk::Continue(false)
-                    }));
-                    return gtk::Continue(false);
-                },
-            };
-        }
+    rx.attach(clone!(wm_util move |windows_result| {
+        match windows_result {
+            Ok(windows) => {
+                wm_util.emit_value(
+                    Event::Windows,
+                    EventValue::Windows(windows),
+                );
+            },
+            Err(err) => {
+                warn!("{}, restarting thread", err.to_lowercase());
+                gtk::timeout_add(1000, clone!(wm_util move || {
+                    listen(&wm_util);
+                    gtk::Continue(false)
+                }));
+                return gtk::Continue(false);
+            },
+        };
         gtk::Continue(true)
     }));
 }
@@ -187,42 +179,21 @@ fn get_visible(conn: &xcb::Connection, window: xcb::Window) -> bool {
         .unwrap_or_else(|_| false)
 }
 
-fn get_client_list(
-    conn: &xcb::Connection,
-    atoms: &wm::atom::Atoms,
-    screen: &xcb::Screen,
-) -> Vec<xcb::Window> {
-    xcb::get_property(
-        &conn,
-        false,
-        screen.root(),
-        atoms.get(wm::atom::_NET_CLIENT_LIST),
-        xcb::ATOM_WINDOW,
-        0,
-        8,
-    )
-        .get_reply()
-        .and_then(|r| Ok(r.value().to_vec()))
-        .unwrap_or_else(|_| Vec::new())
+fn get_client_list(conn: &wm::xcb::XConnection) -> Vec<xcb::Window> {
+    conn.get_property(conn.get_root(), wm::atom::_NET_CLIENT_LIST, xcb::ATOM_WINDOW)
 }
 
 fn add_window(
     conn: &xcb::Connection,
+    events: &wm::xcb::Subscription,
     window: xcb::Window,
 ) -> XWindowData {
-    xcb::change_window_attributes(
-        &conn,
+    events.watch(
         window,
-        &[
-            (
-                xcb::CW_EVENT_MASK,
-                xcb::EVENT_MASK_PROPERTY_CHANGE
-                | xcb::EVENT_MASK_STRUCTURE_NOTIFY
-                | xcb::EVENT_MASK_VISIBILITY_CHANGE
-            ),
-        ],
+        xcb::EVENT_MASK_PROPERTY_CHANGE
+        | xcb::EVENT_MASK_STRUCTURE_NOTIFY
+        | xcb::EVENT_MASK_VISIBILITY_CHANGE,
     );
-    conn.flush();
 
     let name = get_name(&conn, window);
     let visible = get_visible(&conn, window);